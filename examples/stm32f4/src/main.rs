@@ -5,7 +5,7 @@ use defmt::*;
 use embassy_executor::Spawner;
 use embassy_stm32::time::Hertz;
 use embassy_stm32::{bind_interrupts, i2c, peripherals};
-use embassy_time::{Duration, Timer};
+use embassy_time::{Delay, Duration, Timer};
 
 use pas_co2_rs::regs::*;
 use pas_co2_rs::*;
@@ -44,6 +44,12 @@ async fn main(_spawner: Spawner) {
     // Obtain an instance of the driver
     let mut pas_co2 = PasCo2::new(i2c);
 
+    // Wait until the sensor is ready, clear stale errors and check communication
+    pas_co2.init(Delay, 2000).await.unwrap();
+
+    // Fail fast if the wrong sensor is connected
+    info!("Sensor: {}", pas_co2.probe().await.unwrap());
+
     info!("Status: {}", pas_co2.get_status().await.unwrap());
 
     // Set to idle mode (default)
@@ -54,32 +60,23 @@ async fn main(_spawner: Spawner) {
 
     pas_co2.set_measurement_mode(mode).await.unwrap();
 
-    let pressure: u16 = 950; //hPa
+    let pressure = PressureHpa::new(950).unwrap();
     pas_co2.set_pressure_compensation(pressure).await.unwrap();
 
-    defmt::info!("Testing write -> read");
-    let test_val = 0b1010_0101;
-    let read_val = pas_co2.test_write_read(test_val).await.unwrap();
-    defmt::assert_eq!(test_val, read_val);
-
-    pas_co2.clear_status().await.unwrap();
-
     // Perform a forced calibration/compensation at 490 ppm reference
 
     /*
-        pas_co2
-            .do_forced_compensation(490, embassy_time::Delay)
+        let reference = CalibrationPpm::new(490).unwrap();
+        let report = pas_co2
+            .do_forced_compensation(ForcedCompensationConfig::new(reference), Delay)
             .await
             .unwrap();
+        info!("Compensation readings: {}", report.readings());
     */
 
     loop {
         defmt::info!("Starting measurement");
-        pas_co2.start_measurement().await.unwrap();
-
-        Timer::after_millis(1150).await;
-
-        let co2_ppm = pas_co2.get_co2_ppm().await.unwrap();
+        let co2_ppm = pas_co2.measure_single_shot(Delay, 2000).await.unwrap();
 
         info!("CO2: {} ppm", co2_ppm);
 
@@ -0,0 +1,228 @@
+//! Classification of CO2 readings into indoor air quality categories
+//!
+//! [AirQualityThresholds] maps a reading to an [AirQuality], which recommends an [Action].
+//! Threshold sets based on EN 16798-1 and ASHRAE guidance are provided, custom ones can be
+//! created with [AirQualityThresholds::new()].
+//!
+//! ```
+//! use pas_co2_rs::air_quality::{Action, AirQuality, AirQualityThresholds};
+//!
+//! let thresholds = AirQualityThresholds::en_16798_1(420);
+//! let quality = thresholds.classify(1300);
+//! assert_eq!(quality, AirQuality::Moderate);
+//! assert_eq!(quality.recommended_action(), Action::ConsiderVentilating);
+//! ```
+
+/// Indoor air quality category, from best to worst
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum AirQuality {
+    Excellent,
+    Good,
+    Moderate,
+    Poor,
+    Unhealthy,
+}
+
+impl AirQuality {
+    /// Action recommended for this category
+    pub fn recommended_action(self) -> Action {
+        match self {
+            AirQuality::Excellent | AirQuality::Good => Action::None,
+            AirQuality::Moderate => Action::ConsiderVentilating,
+            AirQuality::Poor => Action::Ventilate,
+            AirQuality::Unhealthy => Action::LeaveRoom,
+        }
+    }
+}
+
+/// Action recommended for an [AirQuality]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    /// No action necessary
+    None,
+    /// Ventilate when convenient, e.g. concentration and well-being may start to suffer
+    ConsiderVentilating,
+    /// Ventilate now, e.g. open windows or increase the ventilation rate
+    Ventilate,
+    /// Leave the room until it has been ventilated
+    LeaveRoom,
+}
+
+impl Action {
+    /// Short description of the action, e.g. for displays
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::None => "No action necessary",
+            Action::ConsiderVentilating => "Consider ventilating",
+            Action::Ventilate => "Ventilate now",
+            Action::LeaveRoom => "Leave the room and ventilate",
+        }
+    }
+}
+
+/// The thresholds are not strictly ascending, see [AirQualityThresholds::new()]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct InvalidThresholds;
+
+/// Upper limits of the [AirQuality] categories in PPM
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct AirQualityThresholds {
+    /// Highest reading of [AirQuality::Excellent], [AirQuality::Good], [AirQuality::Moderate]
+    /// and [AirQuality::Poor]. Readings above are [AirQuality::Unhealthy].
+    upper_ppm: [i16; 4],
+}
+
+impl AirQualityThresholds {
+    /// ASHRAE-style guidance
+    ///
+    /// Moderate up to 1100 ppm, i.e. 700 ppm above a typical outdoor concentration as targeted
+    /// by ASHRAE 62.1 ventilation rates. Excellent up to 600 ppm, good up to 800 ppm, poor up
+    /// to 2500 ppm.
+    pub const ASHRAE: Self = Self {
+        upper_ppm: [600, 800, 1100, 2500],
+    };
+
+    /// Highest outdoor concentration accepted by [Self::en_16798_1()], so moderate air stays
+    /// below the exposure limit of 5000 ppm
+    pub const MAX_OUTDOOR_PPM: i16 = 5000 - 1350 - 1;
+
+    /// Categories I to IV of EN 16798-1 for the given outdoor concentration
+    ///
+    /// Excellent (I), good (II) and moderate (III) up to 550, 800 and 1350 ppm above
+    /// `outdoor_ppm`. Above is poor (IV), up to the workplace exposure limit of 5000 ppm, which
+    /// is unhealthy. `outdoor_ppm` is clamped to 0 to [Self::MAX_OUTDOOR_PPM], so the
+    /// thresholds are always strictly ascending.
+    pub const fn en_16798_1(outdoor_ppm: i16) -> Self {
+        let outdoor_ppm = if outdoor_ppm < 0 {
+            0
+        } else if outdoor_ppm > Self::MAX_OUTDOOR_PPM {
+            Self::MAX_OUTDOOR_PPM
+        } else {
+            outdoor_ppm
+        };
+        Self {
+            upper_ppm: [
+                outdoor_ppm + 550,
+                outdoor_ppm + 800,
+                outdoor_ppm + 1350,
+                5000,
+            ],
+        }
+    }
+
+    /// Custom thresholds, the highest readings of excellent, good, moderate and poor air
+    ///
+    /// Fails unless the thresholds are strictly ascending.
+    pub fn new(upper_ppm: [i16; 4]) -> Result<Self, InvalidThresholds> {
+        if upper_ppm.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(InvalidThresholds);
+        }
+        Ok(Self { upper_ppm })
+    }
+
+    /// Highest readings of excellent, good, moderate and poor air
+    pub fn upper_ppm(&self) -> [i16; 4] {
+        self.upper_ppm
+    }
+
+    /// Category of a reading
+    pub fn classify(&self, co2_ppm: i16) -> AirQuality {
+        match self.upper_ppm.iter().position(|&upper| co2_ppm <= upper) {
+            Some(0) => AirQuality::Excellent,
+            Some(1) => AirQuality::Good,
+            Some(2) => AirQuality::Moderate,
+            Some(_) => AirQuality::Poor,
+            None => AirQuality::Unhealthy,
+        }
+    }
+}
+
+impl Default for AirQualityThresholds {
+    /// EN 16798-1 at an outdoor concentration of 400 ppm
+    fn default() -> Self {
+        Self::en_16798_1(400)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_en_16798_1() {
+        let thresholds = AirQualityThresholds::default();
+        assert_eq!(thresholds.upper_ppm(), [950, 1200, 1750, 5000]);
+        assert_eq!(thresholds.classify(400), AirQuality::Excellent);
+        assert_eq!(thresholds.classify(950), AirQuality::Excellent);
+        assert_eq!(thresholds.classify(951), AirQuality::Good);
+        assert_eq!(thresholds.classify(1500), AirQuality::Moderate);
+        assert_eq!(thresholds.classify(1751), AirQuality::Poor);
+        assert_eq!(thresholds.classify(5001), AirQuality::Unhealthy);
+
+        assert_eq!(
+            AirQualityThresholds::en_16798_1(500).classify(1000),
+            AirQuality::Excellent
+        );
+    }
+
+    #[test]
+    fn test_en_16798_1_outdoor_range() {
+        let max = AirQualityThresholds::MAX_OUTDOOR_PPM;
+        let highest = AirQualityThresholds::en_16798_1(max);
+        assert_eq!(highest.upper_ppm(), [4199, 4449, 4999, 5000]);
+        assert_eq!(AirQualityThresholds::new(highest.upper_ppm()), Ok(highest));
+
+        // Clamped, so the exposure limit still applies
+        for outdoor_ppm in [max + 1, 4000, i16::MAX] {
+            let thresholds = AirQualityThresholds::en_16798_1(outdoor_ppm);
+            assert_eq!(thresholds, highest);
+            assert_eq!(thresholds.classify(5020), AirQuality::Unhealthy);
+        }
+        for outdoor_ppm in [-1, i16::MIN] {
+            assert_eq!(
+                AirQualityThresholds::en_16798_1(outdoor_ppm).upper_ppm(),
+                [550, 800, 1350, 5000]
+            );
+        }
+    }
+
+    #[test]
+    fn test_ashrae() {
+        let thresholds = AirQualityThresholds::ASHRAE;
+        assert_eq!(thresholds.classify(-1), AirQuality::Excellent);
+        assert_eq!(thresholds.classify(700), AirQuality::Good);
+        assert_eq!(thresholds.classify(1100), AirQuality::Moderate);
+        assert_eq!(thresholds.classify(2000), AirQuality::Poor);
+        assert_eq!(thresholds.classify(i16::MAX), AirQuality::Unhealthy);
+    }
+
+    #[test]
+    fn test_custom() {
+        let thresholds = AirQualityThresholds::new([500, 700, 1000, 1500]).unwrap();
+        assert_eq!(thresholds.classify(1200), AirQuality::Poor);
+
+        assert_eq!(
+            AirQualityThresholds::new([500, 700, 700, 1500]),
+            Err(InvalidThresholds)
+        );
+    }
+
+    #[test]
+    fn test_recommended_action() {
+        assert_eq!(AirQuality::Good.recommended_action(), Action::None);
+        assert_eq!(AirQuality::Poor.recommended_action(), Action::Ventilate);
+        assert_eq!(
+            AirQuality::Unhealthy.recommended_action().description(),
+            "Leave the room and ventilate"
+        );
+        assert!(AirQuality::Excellent < AirQuality::Unhealthy);
+    }
+}
@@ -0,0 +1,263 @@
+//! Software alarm with multiple levels, hysteresis and debouncing
+//!
+//! The sensor's hardware alarm supports a single threshold in one direction. [AlarmEngine]
+//! instead evaluates every reading against a list of [AlarmLevel]s, each with its own rising
+//! and falling threshold, and only reports a new level once it persisted for a minimum time.
+//!
+//! ```
+//! use pas_co2_rs::alarm::{AlarmEngine, AlarmLevel};
+//!
+//! const LEVELS: [AlarmLevel; 2] = [
+//!     AlarmLevel::new("elevated", 1000, 900),
+//!     AlarmLevel::new("high", 1400, 1300),
+//! ];
+//! let mut alarm = AlarmEngine::new(&LEVELS, 0).unwrap();
+//!
+//! let event = alarm.update(1050, 0).unwrap();
+//! assert_eq!(event.current.unwrap().name, "elevated");
+//! // Within the hysteresis band, the level is kept
+//! assert!(alarm.update(950, 1000).is_none());
+//! assert!(alarm.update(850, 2000).unwrap().current.is_none());
+//! ```
+
+/// Level of an [AlarmEngine]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct AlarmLevel {
+    /// Name reported with [AlarmEvent]s
+    pub name: &'static str,
+    /// The level is entered once a reading reaches this value
+    pub rising_ppm: i16,
+    /// The level is left once a reading falls below this value
+    pub falling_ppm: i16,
+}
+
+impl AlarmLevel {
+    /// Create a level, `falling_ppm` must not be above `rising_ppm`
+    pub const fn new(name: &'static str, rising_ppm: i16, falling_ppm: i16) -> Self {
+        Self {
+            name,
+            rising_ppm,
+            falling_ppm,
+        }
+    }
+}
+
+/// Invalid list of [AlarmLevel]s, see [AlarmEngine::new()]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AlarmConfigError {
+    /// A level's falling threshold is above its rising threshold
+    InvalidHysteresis,
+    /// The rising thresholds are not strictly ascending
+    Unsorted,
+}
+
+/// Transition between two levels of an [AlarmEngine]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct AlarmEvent<'a> {
+    /// Level before the transition, `None` if below all levels
+    pub previous: Option<&'a AlarmLevel>,
+    /// Level after the transition, `None` if below all levels
+    pub current: Option<&'a AlarmLevel>,
+    /// Reading that caused the transition
+    pub co2_ppm: i16,
+    /// Time of the reading that caused the transition
+    pub timestamp_ms: u64,
+}
+
+impl AlarmEvent<'_> {
+    /// Whether the transition is to a higher level
+    pub fn is_rising(&self) -> bool {
+        match (self.previous, self.current) {
+            (Some(previous), Some(current)) => current.rising_ppm > previous.rising_ppm,
+            (None, _) => true,
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// Evaluates readings against [AlarmLevel]s and emits an [AlarmEvent] on each level transition
+pub struct AlarmEngine<'a> {
+    levels: &'a [AlarmLevel],
+    min_dwell_ms: u64,
+    /// Number of levels entered, i.e. 0 if below all levels
+    current: usize,
+    /// Level the readings point to, and since when, if different from `current`
+    pending: Option<(usize, u64)>,
+}
+
+impl<'a> AlarmEngine<'a> {
+    /// Create an engine for `levels`, sorted by ascending rising threshold
+    ///
+    /// A level change is only reported once the readings point to the new level for at least
+    /// `min_dwell_ms`. Use 0 to report level changes at once.
+    pub fn new(levels: &'a [AlarmLevel], min_dwell_ms: u64) -> Result<Self, AlarmConfigError> {
+        if levels
+            .iter()
+            .any(|level| level.falling_ppm > level.rising_ppm)
+        {
+            return Err(AlarmConfigError::InvalidHysteresis);
+        }
+        if levels
+            .windows(2)
+            .any(|pair| pair[0].rising_ppm >= pair[1].rising_ppm)
+        {
+            return Err(AlarmConfigError::Unsorted);
+        }
+
+        Ok(Self {
+            levels,
+            min_dwell_ms,
+            current: 0,
+            pending: None,
+        })
+    }
+
+    /// Current level, `None` if below all levels
+    pub fn level(&self) -> Option<&'a AlarmLevel> {
+        self.current.checked_sub(1).map(|i| &self.levels[i])
+    }
+
+    /// Evaluate a reading taken at `timestamp_ms`
+    ///
+    /// Timestamps must not decrease. Returns an event if the level changed.
+    pub fn update(&mut self, co2_ppm: i16, timestamp_ms: u64) -> Option<AlarmEvent<'a>> {
+        let target = self.target(co2_ppm);
+        if target == self.current {
+            self.pending = None;
+            return None;
+        }
+
+        let since_ms = match self.pending {
+            Some((pending, since_ms)) if pending == target => since_ms,
+            _ => timestamp_ms,
+        };
+        if timestamp_ms.saturating_sub(since_ms) < self.min_dwell_ms {
+            self.pending = Some((target, since_ms));
+            return None;
+        }
+
+        let previous = self.level();
+        self.current = target;
+        self.pending = None;
+        Some(AlarmEvent {
+            previous,
+            current: self.level(),
+            co2_ppm,
+            timestamp_ms,
+        })
+    }
+
+    /// Level the reading points to, considering the hysteresis of the current level
+    fn target(&self, co2_ppm: i16) -> usize {
+        let rising = self
+            .levels
+            .iter()
+            .take_while(|level| co2_ppm >= level.rising_ppm)
+            .count();
+        if rising > self.current {
+            return rising;
+        }
+
+        let mut target = self.current;
+        while target > 0 && co2_ppm < self.levels[target - 1].falling_ppm {
+            target -= 1;
+        }
+        target
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const LEVELS: [AlarmLevel; 3] = [
+        AlarmLevel::new("elevated", 1000, 900),
+        AlarmLevel::new("high", 1400, 1300),
+        AlarmLevel::new("critical", 2000, 1900),
+    ];
+
+    fn level_name(event: Option<AlarmEvent<'_>>) -> Option<&'static str> {
+        event.and_then(|e| e.current).map(|level| level.name)
+    }
+
+    #[test]
+    fn test_invalid_levels() {
+        let inverted = [AlarmLevel::new("elevated", 1000, 1100)];
+        assert_eq!(
+            AlarmEngine::new(&inverted, 0).err(),
+            Some(AlarmConfigError::InvalidHysteresis)
+        );
+
+        let unsorted = [LEVELS[1], LEVELS[0]];
+        assert_eq!(
+            AlarmEngine::new(&unsorted, 0).err(),
+            Some(AlarmConfigError::Unsorted)
+        );
+    }
+
+    #[test]
+    fn test_hysteresis() {
+        let mut alarm = AlarmEngine::new(&LEVELS, 0).unwrap();
+        assert!(alarm.update(990, 0).is_none());
+
+        let event = alarm.update(1000, 1).unwrap();
+        assert_eq!(event.previous, None);
+        assert_eq!(event.current, Some(&LEVELS[0]));
+        assert!(event.is_rising());
+
+        // Flapping around the threshold keeps the level
+        for (t, ppm) in [(2, 990), (3, 1010), (4, 950), (5, 1000), (6, 900)] {
+            assert!(alarm.update(ppm, t).is_none());
+        }
+
+        let event = alarm.update(899, 7).unwrap();
+        assert_eq!(event.current, None);
+        assert!(!event.is_rising());
+        assert_eq!(alarm.level(), None);
+    }
+
+    #[test]
+    fn test_skipping_levels() {
+        let mut alarm = AlarmEngine::new(&LEVELS, 0).unwrap();
+        assert_eq!(level_name(alarm.update(2100, 0)), Some("critical"));
+
+        // Falls through two hysteresis bands at once
+        let event = alarm.update(1200, 1).unwrap();
+        assert_eq!(event.previous, Some(&LEVELS[2]));
+        assert_eq!(event.current, Some(&LEVELS[0]));
+        assert!(!event.is_rising());
+
+        assert_eq!(level_name(alarm.update(1500, 2)), Some("high"));
+    }
+
+    #[test]
+    fn test_min_dwell() {
+        let mut alarm = AlarmEngine::new(&LEVELS, 10_000).unwrap();
+        assert!(alarm.update(1100, 0).is_none());
+        assert!(alarm.update(1100, 5_000).is_none());
+
+        // A short dip below the threshold restarts the dwell time
+        assert!(alarm.update(800, 6_000).is_none());
+        assert!(alarm.update(1100, 7_000).is_none());
+        assert!(alarm.update(1100, 16_000).is_none());
+
+        let event = alarm.update(1100, 17_000).unwrap();
+        assert_eq!(event.current, Some(&LEVELS[0]));
+        assert_eq!(event.timestamp_ms, 17_000);
+        assert_eq!(event.co2_ppm, 1100);
+    }
+
+    #[test]
+    fn test_min_dwell_changing_target() {
+        let mut alarm = AlarmEngine::new(&LEVELS, 10_000).unwrap();
+        assert!(alarm.update(1100, 0).is_none());
+
+        // A different target level restarts the dwell time
+        assert!(alarm.update(1500, 5_000).is_none());
+        assert!(alarm.update(1500, 10_000).is_none());
+        assert_eq!(level_name(alarm.update(1500, 15_000)), Some("high"));
+    }
+}
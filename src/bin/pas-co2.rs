@@ -0,0 +1,183 @@
+//! Command-line tool for a PAS CO2 sensor on a Linux I2C bus
+//!
+//! Built with the `cli` feature. `--simulate` uses the [Simulator] instead of the bus.
+
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::str::FromStr;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::I2c;
+use linux_embedded_hal::{Delay, I2cdev};
+
+use pas_co2_rs::blocking::PasCo2;
+use pas_co2_rs::regs::{CalibrationPpm, MeasurementPeriod, PressureHpa, SoftReset};
+use pas_co2_rs::simulator::Simulator;
+use pas_co2_rs::{Error, ForcedCompensationConfig};
+
+/// Timeout of a single shot measurement
+const READ_TIMEOUT_MS: u32 = 2000;
+
+#[derive(Parser)]
+#[command(version, about = "Access an Infineon XENSIV PAS CO2 sensor")]
+struct Cli {
+    /// I2C bus the sensor is connected to
+    #[arg(long, default_value = "/dev/i2c-1")]
+    bus: PathBuf,
+
+    /// Use a simulated sensor instead of the I2C bus
+    #[arg(long)]
+    simulate: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the product id and status
+    Status,
+    /// Perform a single measurement and print the CO2 concentration in ppm
+    Read,
+    /// Measure in continuous mode and print every reading
+    Monitor {
+        /// Time between two measurements in seconds (5 to 4095)
+        #[arg(long, default_value = "10", value_parser = parse_ranged::<MeasurementPeriod, u16>)]
+        period: MeasurementPeriod,
+        /// Stop after this many readings
+        #[arg(long)]
+        count: Option<usize>,
+    },
+    /// Print all configuration registers
+    Dump,
+    /// Set the ambient pressure used for pressure compensation
+    SetPressure {
+        /// Pressure in hPa (750 to 1150)
+        #[arg(value_parser = parse_ranged::<PressureHpa, u16>)]
+        hpa: PressureHpa,
+    },
+    /// Perform a forced compensation while the sensor is exposed to a known CO2 concentration
+    Calibrate {
+        /// CO2 concentration in ppm (350 to 900)
+        #[arg(long, value_parser = parse_ranged::<CalibrationPpm, i16>)]
+        reference: CalibrationPpm,
+        /// Save the compensation to the sensor's non-volatile memory
+        #[arg(long)]
+        save: bool,
+    },
+    /// Send a soft reset command
+    Reset {
+        #[arg(value_enum)]
+        kind: ResetKind,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ResetKind {
+    /// Reset all registers to their default values
+    Soft,
+    /// Reset the automatic baseline offset compensation context
+    Aboc,
+    /// Save the forced compensation offset to non-volatile memory
+    SaveCalibration,
+    /// Reset the forced compensation correction factor
+    ResetCalibration,
+    /// Disable the stepwise reactive IIR filter
+    DisableIir,
+    /// Enable the stepwise reactive IIR filter
+    EnableIir,
+}
+
+impl From<ResetKind> for SoftReset {
+    fn from(kind: ResetKind) -> Self {
+        match kind {
+            ResetKind::Soft => SoftReset::SoftReset,
+            ResetKind::Aboc => SoftReset::AbocReset,
+            ResetKind::SaveCalibration => SoftReset::SaveForceCalibNvm,
+            ResetKind::ResetCalibration => SoftReset::ResetForcedCalibCorrectionFactor,
+            ResetKind::DisableIir => SoftReset::DisableStepwiseReractiveIirFilter,
+            ResetKind::EnableIir => SoftReset::EnableStepwiseReaciveIirFilter,
+        }
+    }
+}
+
+/// Parse a value of type `V` and check its range by converting it to `T`
+fn parse_ranged<T, V>(s: &str) -> Result<T, String>
+where
+    T: TryFrom<V>,
+    V: FromStr,
+    V::Err: Display,
+{
+    let value = s.parse::<V>().map_err(|e| e.to_string())?;
+    T::try_from(value).map_err(|_| String::from("value out of range"))
+}
+
+fn run<I2C, D>(i2c: I2C, mut delay: D, command: Command) -> Result<(), Error<I2C::Error>>
+where
+    I2C: I2c,
+    D: DelayNs,
+{
+    let mut pas_co2 = PasCo2::new(i2c);
+    pas_co2.probe()?;
+
+    match command {
+        Command::Status => {
+            println!("{:#?}", pas_co2.identify()?);
+            println!("{:#?}", pas_co2.get_status()?);
+        }
+        Command::Read => {
+            let co2_ppm = pas_co2.measure_single_shot(&mut delay, READ_TIMEOUT_MS)?;
+            println!("{co2_ppm} ppm");
+        }
+        Command::Monitor { period, count } => {
+            let readings = pas_co2.start_continuous(period, &mut delay)?;
+            for reading in readings.take(count.unwrap_or(usize::MAX)) {
+                println!("{} ppm", reading?);
+            }
+        }
+        Command::Dump => println!("{:#?}", pas_co2.read_snapshot()?),
+        Command::SetPressure { hpa } => {
+            pas_co2.set_pressure_compensation(hpa)?;
+            println!("Pressure compensation set to {} hPa", hpa.get());
+        }
+        Command::Calibrate { reference, save } => {
+            let config = ForcedCompensationConfig {
+                save_to_nvm: save,
+                ..ForcedCompensationConfig::new(reference)
+            };
+            let report = pas_co2.do_forced_compensation(config, &mut delay)?;
+            println!(
+                "Compensated to {} ppm, readings: {:?}",
+                reference.get(),
+                report.readings()
+            );
+        }
+        Command::Reset { kind } => pas_co2.soft_reset(kind.into())?,
+    }
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = if cli.simulate {
+        let sim = Simulator::new();
+        run(sim.clone(), sim.delay(), cli.command).map_err(|e| format!("{e:?}"))
+    } else {
+        match I2cdev::new(&cli.bus) {
+            Ok(i2c) => run(i2c, Delay, cli.command).map_err(|e| format!("{e:?}")),
+            Err(e) => Err(format!("Cannot open {}: {e}", cli.bus.display())),
+        }
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
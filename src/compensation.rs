@@ -0,0 +1,181 @@
+use crate::regs::{CalibrationPpm, MeasurementPeriod};
+
+/// Maximum number of measurements of a forced compensation
+pub const MAX_FORCED_COMPENSATION_ITERATIONS: usize = 16;
+
+/// Configuration of a forced compensation, see [PasCo2::do_forced_compensation()](crate::PasCo2::do_forced_compensation())
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ForcedCompensationConfig {
+    /// CO2 concentration the sensor is exposed to during the compensation
+    pub reference: CalibrationPpm,
+    /// Number of measurements, 1 to [MAX_FORCED_COMPENSATION_ITERATIONS]
+    pub iterations: usize,
+    /// Measurement period during the compensation
+    pub period: MeasurementPeriod,
+    /// Time after which the compensation is aborted with [Error::Timeout](crate::Error::Timeout)
+    pub timeout_ms: u32,
+    /// Save the compensation offset to the sensor's NVM, see [SoftReset::SaveForceCalibNvm](crate::regs::SoftReset::SaveForceCalibNvm)
+    pub save_to_nvm: bool,
+}
+
+impl ForcedCompensationConfig {
+    /// Compensate to `reference` with 3 measurements, 10 s apart, within 40 s without saving to NVM
+    pub fn new(reference: CalibrationPpm) -> Self {
+        Self {
+            reference,
+            iterations: 3,
+            period: MeasurementPeriod::new(10).unwrap(),
+            timeout_ms: 40_000,
+            save_to_nvm: false,
+        }
+    }
+}
+
+/// Readings taken during a forced compensation
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ForcedCompensationReport {
+    readings: [i16; MAX_FORCED_COMPENSATION_ITERATIONS],
+    len: usize,
+}
+
+impl ForcedCompensationReport {
+    fn new() -> Self {
+        Self {
+            readings: [0; MAX_FORCED_COMPENSATION_ITERATIONS],
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, co2_ppm: i16) {
+        self.readings[self.len] = co2_ppm;
+        self.len += 1;
+    }
+
+    /// CO2 readings in PPM in the order they were taken
+    pub fn readings(&self) -> &[i16] {
+        &self.readings[..self.len]
+    }
+}
+
+// See driver.rs on how the async and blocking variants are generated.
+#[maybe_async_cfg::maybe(sync(self = "blocking"), async(self = "asynchronous"))]
+pub mod compensation {
+    #[maybe_async_cfg::only_if(async)]
+    use crate::driver::asynchronous::PasCo2;
+    #[maybe_async_cfg::only_if(sync)]
+    use crate::driver::blocking::PasCo2;
+    #[maybe_async_cfg::only_if(async)]
+    use crate::interface::asynchronous::Interface;
+    #[maybe_async_cfg::only_if(sync)]
+    use crate::interface::blocking::Interface;
+    #[maybe_async_cfg::only_if(sync)]
+    use embedded_hal::delay::DelayNs;
+    #[maybe_async_cfg::only_if(async)]
+    use embedded_hal_async::delay::DelayNs;
+
+    use super::{
+        ForcedCompensationConfig, ForcedCompensationReport, MAX_FORCED_COMPENSATION_ITERATIONS,
+    };
+    use crate::regs::{
+        BaselineOffsetCompensation, MeasurementMode, MeasurementPeriod, OperatingMode, SoftReset,
+    };
+    use crate::Error;
+
+    impl<T> PasCo2<T>
+    where
+        T: Interface,
+    {
+        /// Perform a forced compensation to the reference value in `config`
+        ///
+        /// The sensor must be exposed to the reference CO2 concentration during the whole
+        /// procedure. Measures in continuous mode with forced compensation enabled until
+        /// `config.iterations` readings were taken, then saves the offset to NVM if requested.
+        ///
+        /// Afterwards, the previous measurement period and mode are restored, also on errors.
+        /// A single shot measurement is not restarted.
+        pub async fn do_forced_compensation(
+            &mut self,
+            config: ForcedCompensationConfig,
+            mut delay: impl DelayNs,
+        ) -> Result<ForcedCompensationReport, Error<T::Error>> {
+            if !(1..=MAX_FORCED_COMPENSATION_ITERATIONS).contains(&config.iterations) {
+                return Err(Error::OutOfRange);
+            }
+
+            #[cfg(feature = "defmt")]
+            defmt::info!(
+                "Entering forced compensation with reference: {}",
+                config.reference
+            );
+
+            let previous_period = self.get_measurement_period().await?;
+            let mut previous_mode = self.get_measurement_mode().await?;
+            if previous_mode.operating_mode == OperatingMode::SingleShot {
+                previous_mode.operating_mode = OperatingMode::Idle;
+            }
+
+            let result = self.run_forced_compensation(&config, &mut delay).await;
+
+            // Restore the previous state even if the compensation failed
+            let restored = self
+                .restore_measurement_config(previous_period, previous_mode)
+                .await;
+
+            let report = result?;
+            restored?;
+            Ok(report)
+        }
+
+        async fn restore_measurement_config(
+            &mut self,
+            period: MeasurementPeriod,
+            mode: MeasurementMode,
+        ) -> Result<(), Error<T::Error>> {
+            // The measurement period must only be changed in idle mode
+            self.set_idle().await?;
+            self.set_measurement_period(period).await?;
+            self.set_measurement_mode(mode).await
+        }
+
+        async fn run_forced_compensation(
+            &mut self,
+            config: &ForcedCompensationConfig,
+            delay: &mut impl DelayNs,
+        ) -> Result<ForcedCompensationReport, Error<T::Error>> {
+            // The measurement period must only be changed in idle mode
+            self.set_idle().await?;
+            self.set_measurement_period(config.period).await?;
+            self.set_calibration_reference(config.reference).await?;
+
+            // Reading the status discards a stale data ready flag of a previous measurement
+            self.get_measurement_status().await?;
+
+            let mut mode = self.get_measurement_mode().await?;
+            mode.baseline_offset_comp = BaselineOffsetCompensation::Forced;
+            mode.operating_mode = OperatingMode::Continuous;
+            self.set_measurement_mode(mode).await?;
+
+            let mut report = ForcedCompensationReport::new();
+            let mut waited_ms = 0;
+            while report.readings().len() < config.iterations {
+                let remaining_ms = config.timeout_ms.saturating_sub(waited_ms);
+                waited_ms += self.wait_for_data(delay, remaining_ms).await?;
+
+                let co2_ppm = self.get_co2_ppm().await?;
+                #[cfg(feature = "defmt")]
+                defmt::info!("Read CO2 PPM: {}", co2_ppm);
+                report.push(co2_ppm);
+            }
+
+            // The offset must be saved in idle mode
+            self.set_idle().await?;
+            if config.save_to_nvm {
+                self.soft_reset(SoftReset::SaveForceCalibNvm).await?;
+            }
+
+            Ok(report)
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Conversion of CO2 readings from PPM to mass concentration
+//!
+//! The mass concentration of a gas depends on temperature and pressure (ideal gas law):
+//! `µg/m³ = ppm * p * M / (R * T)` with the molar mass `M` of CO2 (44.0095 g/mol) and the gas
+//! constant `R`. All math is done in integers, so no FPU is needed.
+//!
+//! ```
+//! use pas_co2_rs::concentration::MassConcentration;
+//! use pas_co2_rs::regs::PressureHpa;
+//!
+//! // 400 ppm at 25 °C and 1013 hPa
+//! let pressure = PressureHpa::new(1013).unwrap();
+//! let concentration = MassConcentration::from_ppm(400, 2500, pressure).unwrap();
+//! assert_eq!(concentration.mg_per_m3(), 719);
+//! ```
+//!
+//! With the driver, [PasCo2::co2_mass_concentration()](crate::PasCo2::co2_mass_concentration())
+//! uses the pressure programmed for pressure compensation.
+
+use crate::regs::{OutOfRangeError, PressureHpa};
+
+/// `M / R` scaled by 10^6
+///
+/// With the pressure in hPa and the temperature in cK, `µg/m³ = ppm * hPa * M / R * 10^4 / cK`.
+/// The additional factor of 100 retains precision.
+const FACTOR: i64 = 5_293_126;
+
+/// 0 °C in centi-Kelvin
+const ZERO_CELSIUS_CENTI_K: i64 = 27315;
+
+/// Lowest temperature accepted by [MassConcentration::from_ppm()] in 0.01 °C
+pub const MIN_TEMPERATURE_CENTI_C: i16 = -10_000;
+
+/// Mass concentration of CO2 in µg/m³
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct MassConcentration(i32);
+
+impl MassConcentration {
+    /// Convert a reading in PPM at `temperature_centi_c` (in 0.01 °C) and `pressure`
+    ///
+    /// Fails for temperatures below [MIN_TEMPERATURE_CENTI_C].
+    pub fn from_ppm(
+        co2_ppm: i16,
+        temperature_centi_c: i16,
+        pressure: PressureHpa,
+    ) -> Result<Self, OutOfRangeError> {
+        if temperature_centi_c < MIN_TEMPERATURE_CENTI_C {
+            return Err(OutOfRangeError);
+        }
+        let temperature_centi_k = i64::from(temperature_centi_c) + ZERO_CELSIUS_CENTI_K;
+
+        let numerator = i64::from(co2_ppm) * i64::from(pressure.get()) * FACTOR;
+        let denominator = temperature_centi_k * 100;
+        // Rounded half up, fits into i32 for all inputs above the minimum temperature
+        let ug_per_m3 = (2 * numerator + denominator).div_euclid(2 * denominator);
+        Ok(Self(ug_per_m3 as i32))
+    }
+
+    /// Mass concentration in µg/m³
+    pub fn ug_per_m3(self) -> i32 {
+        self.0
+    }
+
+    /// Mass concentration in mg/m³, rounded
+    pub fn mg_per_m3(self) -> i32 {
+        (self.0 + 500).div_euclid(1000)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ug_per_m3(co2_ppm: i16, temperature_centi_c: i16, pressure_hpa: u16) -> i32 {
+        let pressure = PressureHpa::new(pressure_hpa).unwrap();
+        MassConcentration::from_ppm(co2_ppm, temperature_centi_c, pressure)
+            .unwrap()
+            .ug_per_m3()
+    }
+
+    #[test]
+    fn test_from_ppm() {
+        // Reference values computed with M = 44.0095 g/mol and R = 8.314462618 J/(mol*K)
+        assert_eq!(ug_per_m3(400, 2500, 1013), 719_361);
+        assert_eq!(ug_per_m3(1000, 0, 1013), 1_963_001);
+        assert_eq!(ug_per_m3(1000, 2000, 900), 1_625_043);
+        assert_eq!(ug_per_m3(0, 2500, 1013), 0);
+        assert_eq!(ug_per_m3(-10, 2500, 1013), -17_984);
+    }
+
+    #[test]
+    fn test_limits() {
+        assert_eq!(
+            ug_per_m3(i16::MAX, MIN_TEMPERATURE_CENTI_C, 1150),
+            115_192_514
+        );
+        assert_eq!(
+            MassConcentration::from_ppm(400, MIN_TEMPERATURE_CENTI_C - 1, PressureHpa::MAX),
+            Err(OutOfRangeError)
+        );
+    }
+
+    #[test]
+    fn test_mg_per_m3() {
+        assert_eq!(MassConcentration(719_499).mg_per_m3(), 719);
+        assert_eq!(MassConcentration(719_500).mg_per_m3(), 720);
+        assert_eq!(MassConcentration(-1_400).mg_per_m3(), -1);
+    }
+}
@@ -0,0 +1,264 @@
+// See driver.rs on how the async and blocking variants are generated.
+#[maybe_async_cfg::maybe(sync(self = "blocking"), async(self = "asynchronous"))]
+pub mod continuous {
+    #[maybe_async_cfg::only_if(async)]
+    use crate::driver::asynchronous::PasCo2;
+    #[maybe_async_cfg::only_if(sync)]
+    use crate::driver::blocking::PasCo2;
+    #[maybe_async_cfg::only_if(async)]
+    use crate::interface::asynchronous::Interface;
+    #[maybe_async_cfg::only_if(sync)]
+    use crate::interface::blocking::Interface;
+    #[maybe_async_cfg::only_if(sync)]
+    use embedded_hal::delay::DelayNs;
+    #[maybe_async_cfg::only_if(async)]
+    use embedded_hal_async::delay::DelayNs;
+
+    use crate::filter::Filter;
+    use crate::regs::{MeasurementPeriod, OperatingMode, SoftReset};
+    use crate::Error;
+
+    /// Time on top of the measurement period after which a reading is considered overdue
+    const READING_TIMEOUT_MARGIN_MS: u32 = 2000;
+
+    /// Readings of the sensor in continuous mode, see [PasCo2::start_continuous()]
+    ///
+    /// Puts the sensor back into idle mode when stopped with [Self::stop()] or dropped.
+    /// The async driver cannot communicate in [Drop], so after dropping the readings it enters
+    /// idle mode right before its next transfer. Use [Self::stop()] to enter idle mode at once.
+    pub struct ContinuousReadings<'a, T: Interface, D: DelayNs> {
+        driver: &'a mut PasCo2<T>,
+        delay: D,
+        timeout_ms: u32,
+        stopped: bool,
+    }
+
+    impl<T> PasCo2<T>
+    where
+        T: Interface,
+    {
+        /// Start measuring every `period` seconds in continuous mode
+        ///
+        /// The returned [ContinuousReadings] yield each new reading once, see
+        /// [ContinuousReadings::next_reading()].
+        pub async fn start_continuous<D: DelayNs>(
+            &mut self,
+            period: MeasurementPeriod,
+            delay: D,
+        ) -> Result<ContinuousReadings<'_, T, D>, Error<T::Error>> {
+            // The measurement period must only be changed in idle mode
+            self.set_idle().await?;
+            self.set_measurement_period(period).await?;
+
+            // Reading the status discards a stale data ready flag of a previous measurement
+            self.get_measurement_status().await?;
+
+            let mut mode = self.get_measurement_mode().await?;
+            mode.operating_mode = OperatingMode::Continuous;
+            self.set_measurement_mode(mode).await?;
+
+            Ok(ContinuousReadings {
+                driver: self,
+                delay,
+                timeout_ms: u32::from(period.get()) * 1000 + READING_TIMEOUT_MARGIN_MS,
+                stopped: false,
+            })
+        }
+    }
+
+    impl<'a, T, D> ContinuousReadings<'a, T, D>
+    where
+        T: Interface,
+        D: DelayNs,
+    {
+        /// Wait for the next reading and return it in PPM
+        ///
+        /// Only returns values the sensor flagged as new, so no reading is returned twice.
+        /// Errors only affect the current reading, i.e. this can be called again afterwards.
+        /// Returns [Error::Timeout] if no reading arrives within the measurement period.
+        pub async fn next_reading(&mut self) -> Result<i16, Error<T::Error>> {
+            self.driver
+                .wait_for_data(&mut self.delay, self.timeout_ms)
+                .await?;
+            self.driver.get_co2_ppm().await
+        }
+
+        /// The driver, for accesses in between readings that keep continuous mode running
+        pub(crate) fn driver(&mut self) -> &mut PasCo2<T> {
+            self.driver
+        }
+
+        /// Stop the continuous measurements and put the sensor into idle mode
+        pub async fn stop(mut self) -> Result<(), Error<T::Error>> {
+            self.enter_idle().await
+        }
+
+        async fn enter_idle(&mut self) -> Result<(), Error<T::Error>> {
+            self.driver.set_idle().await?;
+            self.stopped = true;
+            Ok(())
+        }
+
+        /// Pass the readings through `filter`, see [crate::filter]
+        ///
+        /// To not filter twice, the sensor's stepwise reactive IIR filter is disabled unless
+        /// [PasCo2::iir_filter_enabled()] reports it disabled already. If disabled here, it is
+        /// enabled again when the [FilteredReadings] are stopped or dropped.
+        pub async fn with_filter<F: Filter>(
+            self,
+            filter: F,
+        ) -> Result<FilteredReadings<'a, T, D, F>, Error<T::Error>> {
+            let restore_iir_filter = self.driver.iir_filter_enabled() != Some(false);
+            if restore_iir_filter {
+                self.driver
+                    .soft_reset(SoftReset::DisableStepwiseReractiveIirFilter)
+                    .await?;
+            }
+            Ok(FilteredReadings {
+                readings: self,
+                filter,
+                restore_iir_filter,
+            })
+        }
+    }
+
+    /// Filtered readings of the sensor in continuous mode, see
+    /// [ContinuousReadings::with_filter()]
+    ///
+    /// Like [ContinuousReadings], the async driver enables the sensor's IIR filter again right
+    /// before its next transfer after dropping the readings.
+    pub struct FilteredReadings<'a, T: Interface, D: DelayNs, F: Filter> {
+        readings: ContinuousReadings<'a, T, D>,
+        filter: F,
+        /// The sensor's IIR filter was disabled by [ContinuousReadings::with_filter()]
+        restore_iir_filter: bool,
+    }
+
+    impl<T, D, F> FilteredReadings<'_, T, D, F>
+    where
+        T: Interface,
+        D: DelayNs,
+        F: Filter,
+    {
+        /// Wait for the next reading and return it filtered, see
+        /// [ContinuousReadings::next_reading()]
+        ///
+        /// Errors do not affect the filter.
+        pub async fn next_reading(&mut self) -> Result<i16, Error<T::Error>> {
+            let co2_ppm = self.readings.next_reading().await?;
+            Ok(self.filter.update(co2_ppm))
+        }
+
+        /// Access the filter, e.g. to reset it
+        pub fn filter(&mut self) -> &mut F {
+            &mut self.filter
+        }
+
+        /// Stop the continuous measurements, see [ContinuousReadings::stop()]
+        ///
+        /// Enables the sensor's IIR filter again if [ContinuousReadings::with_filter()] disabled
+        /// it.
+        pub async fn stop(mut self) -> Result<(), Error<T::Error>> {
+            self.readings.enter_idle().await?;
+            if self.restore_iir_filter {
+                self.readings
+                    .driver
+                    .soft_reset(SoftReset::EnableStepwiseReaciveIirFilter)
+                    .await?;
+                self.restore_iir_filter = false;
+            }
+            Ok(())
+        }
+    }
+
+    #[maybe_async_cfg::only_if(sync)]
+    impl<T, D, F> Iterator for FilteredReadings<'_, T, D, F>
+    where
+        T: Interface,
+        D: DelayNs,
+        F: Filter,
+    {
+        type Item = Result<i16, Error<T::Error>>;
+
+        /// Wait for the next filtered reading, see [Self::next_reading()]. Never returns `None`.
+        fn next(&mut self) -> Option<Self::Item> {
+            Some(self.next_reading())
+        }
+    }
+
+    #[maybe_async_cfg::only_if(sync)]
+    impl<T, D> Iterator for ContinuousReadings<'_, T, D>
+    where
+        T: Interface,
+        D: DelayNs,
+    {
+        type Item = Result<i16, Error<T::Error>>;
+
+        /// Wait for the next reading, see [Self::next_reading()]. Never returns `None`.
+        fn next(&mut self) -> Option<Self::Item> {
+            Some(self.next_reading())
+        }
+    }
+
+    #[maybe_async_cfg::only_if(async)]
+    impl<T, D> Drop for ContinuousReadings<'_, T, D>
+    where
+        T: Interface,
+        D: DelayNs,
+    {
+        fn drop(&mut self) {
+            if !self.stopped {
+                self.driver.idle_pending = true;
+            }
+        }
+    }
+
+    #[maybe_async_cfg::only_if(sync)]
+    impl<T, D> Drop for ContinuousReadings<'_, T, D>
+    where
+        T: Interface,
+        D: DelayNs,
+    {
+        fn drop(&mut self) {
+            // Retry before the next transfer if entering idle mode fails now
+            if !self.stopped && self.driver.set_idle().is_err() {
+                self.driver.idle_pending = true;
+            }
+        }
+    }
+
+    #[maybe_async_cfg::only_if(async)]
+    impl<T, D, F> Drop for FilteredReadings<'_, T, D, F>
+    where
+        T: Interface,
+        D: DelayNs,
+        F: Filter,
+    {
+        fn drop(&mut self) {
+            if self.restore_iir_filter {
+                self.readings.driver.iir_filter_pending = true;
+            }
+        }
+    }
+
+    #[maybe_async_cfg::only_if(sync)]
+    impl<T, D, F> Drop for FilteredReadings<'_, T, D, F>
+    where
+        T: Interface,
+        D: DelayNs,
+        F: Filter,
+    {
+        fn drop(&mut self) {
+            // Retry before the next transfer if enabling the filter fails now
+            if self.restore_iir_filter
+                && self
+                    .readings
+                    .driver
+                    .soft_reset(SoftReset::EnableStepwiseReaciveIirFilter)
+                    .is_err()
+            {
+                self.readings.driver.iir_filter_pending = true;
+            }
+        }
+    }
+}
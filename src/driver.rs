@@ -0,0 +1,534 @@
+// The driver is written once against embedded-hal-async. maybe_async_cfg generates an
+// `asynchronous` module from it as-is and a `blocking` module with `async`/`.await` stripped.
+// Items that differ between both (e.g. the embedded-hal traits) are selected with `only_if`.
+#[maybe_async_cfg::maybe(sync(self = "blocking"), async(self = "asynchronous"))]
+pub mod driver {
+    #[maybe_async_cfg::only_if(async)]
+    use crate::interface::asynchronous::{I2cInterface, Interface};
+    #[maybe_async_cfg::only_if(sync)]
+    use crate::interface::blocking::{I2cInterface, Interface};
+    #[cfg(feature = "uart")]
+    #[maybe_async_cfg::only_if(async)]
+    use crate::uart::asynchronous::UartInterface;
+    #[cfg(feature = "uart")]
+    #[maybe_async_cfg::only_if(sync)]
+    use crate::uart::blocking::UartInterface;
+    #[maybe_async_cfg::only_if(sync)]
+    use embedded_hal::{
+        delay::DelayNs,
+        i2c::{I2c, SevenBitAddress},
+    };
+    #[maybe_async_cfg::only_if(async)]
+    use embedded_hal_async::{
+        delay::DelayNs,
+        i2c::{I2c, SevenBitAddress},
+    };
+    #[cfg(feature = "uart")]
+    #[maybe_async_cfg::only_if(sync)]
+    use embedded_io::{Read, Write};
+    #[cfg(feature = "uart")]
+    #[maybe_async_cfg::only_if(async)]
+    use embedded_io_async::{Read, Write};
+
+    use crate::concentration::MassConcentration;
+    use crate::regs::{self, *};
+    use crate::{Error, ResponseError, DATA_READY_POLL_INTERVAL_MS};
+
+    /// Driver for the Infineon XENSIV PAS CO2 sensor
+    pub struct PasCo2<I: Interface> {
+        interface: I,
+        /// Put the sensor into idle mode before the next transfer, see [Self::set_idle()]
+        pub(crate) idle_pending: bool,
+        /// Enable the stepwise reactive IIR filter before the next transfer, after entering
+        /// idle mode if that is pending as well
+        pub(crate) iir_filter_pending: bool,
+        /// See [Self::set_fault_checking()]
+        check_faults: bool,
+        /// See [Self::iir_filter_enabled()]
+        iir_filter: Option<bool>,
+    }
+
+    impl<I2C> PasCo2<I2cInterface<I2C>>
+    where
+        I2C: I2c<SevenBitAddress>,
+    {
+        /// Create a new instance of this driver for a sensor connected via I2C
+        pub fn new(i2c: I2C) -> Self {
+            Self::new_with_interface(I2cInterface::new(i2c))
+        }
+    }
+
+    #[cfg(feature = "uart")]
+    impl<U> PasCo2<UartInterface<U>>
+    where
+        U: Read + Write,
+    {
+        /// Create a new instance of this driver for a sensor connected via UART
+        ///
+        /// See [UartInterface] for the required serial port configuration.
+        pub fn new_uart(uart: U) -> Self {
+            Self::new_with_interface(UartInterface::new(uart))
+        }
+    }
+
+    impl<T> PasCo2<T>
+    where
+        T: Interface,
+    {
+        /// Create a new instance of this driver using the given [Interface]
+        pub fn new_with_interface(interface: T) -> Self {
+            Self {
+                interface,
+                idle_pending: false,
+                iir_filter_pending: false,
+                check_faults: false,
+                iir_filter: None,
+            }
+        }
+
+        /// Check the sensor's [Status] for faults before reading the CO2 value
+        ///
+        /// If enabled, [Self::get_co2_ppm()] and all functions built on it fail with
+        /// [Error::Sensor] while the sensor reports a temperature, voltage or communication error.
+        /// Disabled by default. The faults remain until cleared, see [Self::clear_status()].
+        pub fn set_fault_checking(&mut self, enabled: bool) {
+            self.check_faults = enabled;
+        }
+
+        /// Read the sensor's [ProductId] (product type and firmware revision)
+        pub async fn identify(&mut self) -> Result<ProductId, Error<T::Error>> {
+            self.read_reg_u8(Register::ProdId).await.map(|x| x.into())
+        }
+
+        /// Check that a XENSIV PAS CO2 sensor responds on the [Interface]
+        ///
+        /// Meant to be called at boot to fail fast if no or the wrong part is fitted.
+        /// Returns [Error::UnknownDevice] if the device reports another product type.
+        pub async fn probe(&mut self) -> Result<ProductId, Error<T::Error>> {
+            let id = self.identify().await?;
+            if !id.is_pas_co2() {
+                return Err(Error::UnknownDevice(id));
+            }
+
+            #[cfg(feature = "defmt")]
+            defmt::info!("Found PAS CO2 sensor, firmware revision {}", id.revision);
+
+            Ok(id)
+        }
+
+        /// Obtain the sensor's [Status]
+        pub async fn get_status(&mut self) -> Result<Status, Error<T::Error>> {
+            self.read_reg_u8(Register::SensorStatus)
+                .await
+                .map(|x| x.into())
+        }
+
+        /// Clear temperature, voltage and communication errors from the sensor status
+        pub async fn clear_status(&mut self) -> Result<(), Error<T::Error>> {
+            self.clear_temperature_error().await?;
+            self.clear_voltage_error().await?;
+            self.clear_communication_error().await
+        }
+
+        /// Write bitmask to clear the temperature error bit
+        pub async fn clear_temperature_error(&mut self) -> Result<(), Error<T::Error>> {
+            self.write_reg(Register::SensorStatus, &[0b0000_0100]).await
+        }
+
+        /// Write bitmask to clear the voltage error bit
+        pub async fn clear_voltage_error(&mut self) -> Result<(), Error<T::Error>> {
+            self.write_reg(Register::SensorStatus, &[0b0000_0010]).await
+        }
+
+        /// Write bitmask to clear the communication error bit
+        pub async fn clear_communication_error(&mut self) -> Result<(), Error<T::Error>> {
+            self.write_reg(Register::SensorStatus, &[0b0000_0001]).await
+        }
+
+        /// Time between two measurements in continuous mode
+        pub async fn set_measurement_period(
+            &mut self,
+            period: MeasurementPeriod,
+        ) -> Result<(), Error<T::Error>> {
+            let period = period.get().to_be_bytes();
+
+            self.write_reg(Register::MeasurementRate, &period).await
+        }
+
+        /// Get the time between two measurements in continuous mode
+        pub async fn get_measurement_period(
+            &mut self,
+        ) -> Result<MeasurementPeriod, Error<T::Error>> {
+            let period = self.read_reg_u16(Register::MeasurementRate).await?;
+            MeasurementPeriod::new(period)
+                .map_err(|_| Error::Response(ResponseError::InvalidRegisterValue))
+        }
+
+        /// Configure the [MeasurementMode]
+        pub async fn set_measurement_mode(
+            &mut self,
+            mode: MeasurementMode,
+        ) -> Result<(), Error<T::Error>> {
+            self.write_reg(Register::MeasurementMode, &[mode.into()])
+                .await
+        }
+
+        /// Read the sensor's [MeasurementMode]
+        pub async fn get_measurement_mode(&mut self) -> Result<MeasurementMode, Error<T::Error>> {
+            self.read_reg_u8(Register::MeasurementMode)
+                .await
+                .map(|x| x.into())
+        }
+
+        /// Stop any measurement by setting the operating mode to [OperatingMode::Idle]
+        pub(crate) async fn set_idle(&mut self) -> Result<(), Error<T::Error>> {
+            let mut mode = self.get_measurement_mode().await?;
+            mode.operating_mode = OperatingMode::Idle;
+            self.set_measurement_mode(mode).await
+        }
+
+        /// Start a single measurement.
+        ///
+        /// This function reads the current [MeasurementMode] and sets it
+        /// operating mode to [OperatingMode::SingleShot].
+        pub async fn start_measurement(&mut self) -> Result<(), Error<T::Error>> {
+            let mut mode = self.get_measurement_mode().await?;
+            mode.operating_mode = regs::OperatingMode::SingleShot;
+            self.set_measurement_mode(mode).await
+        }
+
+        /// Get the current CO2 reading in PPM
+        ///
+        /// **Caution**: The user is responsible for starting a measurement and checking whether
+        /// measured data is available. See [Self::get_measurement_status()].
+        /// Fails with [Error::Sensor] on faults if enabled, see [Self::set_fault_checking()].
+        pub async fn get_co2_ppm(&mut self) -> Result<i16, Error<T::Error>> {
+            if self.check_faults {
+                if let Some(fault) = self.get_status().await?.fault() {
+                    return Err(Error::Sensor(fault));
+                }
+            }
+            self.read_reg_i16(Register::Co2Ppm).await
+        }
+
+        /// Perform a single measurement and return the CO2 reading in PPM
+        ///
+        /// Starts a measurement (see [Self::start_measurement()]) and polls the
+        /// [MeasurementStatus] every [DATA_READY_POLL_INTERVAL_MS] until data is ready.
+        /// Afterwards, the [MeasurementStatus] is cleared.
+        ///
+        /// Returns [Error::Timeout] if no data is available after `timeout_ms` milliseconds.
+        /// A measurement takes about one second.
+        pub async fn measure_single_shot(
+            &mut self,
+            mut delay: impl DelayNs,
+            timeout_ms: u32,
+        ) -> Result<i16, Error<T::Error>> {
+            self.measure_single_shot_timed(&mut delay, timeout_ms)
+                .await
+                .map(|(co2_ppm, _)| co2_ppm)
+        }
+
+        /// [Self::measure_single_shot()], also returning the time waited for the measurement
+        pub(crate) async fn measure_single_shot_timed(
+            &mut self,
+            delay: &mut impl DelayNs,
+            timeout_ms: u32,
+        ) -> Result<(i16, u32), Error<T::Error>> {
+            // Reading the status discards a stale data ready flag of a previous measurement
+            self.get_measurement_status().await?;
+            self.start_measurement().await?;
+
+            let waited_ms = self.wait_for_data(delay, timeout_ms).await?;
+            let co2_ppm = self.get_co2_ppm().await?;
+            self.clear_measurement_status().await?;
+
+            Ok((co2_ppm, waited_ms))
+        }
+
+        /// Poll the [MeasurementStatus] until data is ready or `timeout_ms` has passed.
+        ///
+        /// Returns the time waited in milliseconds.
+        /// The sensor may NACK while it is busy measuring, which is not treated as an error.
+        pub(crate) async fn wait_for_data(
+            &mut self,
+            delay: &mut impl DelayNs,
+            timeout_ms: u32,
+        ) -> Result<u32, Error<T::Error>> {
+            let mut waited_ms = 0;
+            loop {
+                match self.get_measurement_status().await {
+                    Ok(status) if status.data_ready => return Ok(waited_ms),
+                    Ok(_) => (),
+                    Err(Error::Interface(e)) if T::is_nack(&e) => {
+                        #[cfg(feature = "defmt")]
+                        defmt::warn!("Got Nack instead of Measurement Status");
+                    }
+                    Err(e) => return Err(e),
+                }
+
+                if waited_ms >= timeout_ms {
+                    return Err(Error::Timeout);
+                }
+                let step = DATA_READY_POLL_INTERVAL_MS.min(timeout_ms - waited_ms);
+                delay.delay_ms(step).await;
+                waited_ms += step;
+            }
+        }
+
+        /// Get the current sensor [MeasurementStatus]
+        pub async fn get_measurement_status(
+            &mut self,
+        ) -> Result<MeasurementStatus, Error<T::Error>> {
+            self.read_reg_u8(Register::MeasurementStatus)
+                .await
+                .map(|x| x.into())
+        }
+
+        /// Clear the int active bit and the alarm bit of the sensor's [MeasurementStatus] register
+        pub async fn clear_measurement_status(&mut self) -> Result<(), Error<T::Error>> {
+            self.clear_int_active().await?;
+            self.clear_alarm().await
+        }
+
+        /// Clear the int active bit of the sensor's [MeasurementStatus] register
+        pub async fn clear_int_active(&mut self) -> Result<(), Error<T::Error>> {
+            // Write bitmask to clear the int pin active bit
+            self.write_reg(Register::MeasurementStatus, &[0b0000_0010])
+                .await
+        }
+
+        /// Clear the the alarm bit of the sensor's [MeasurementStatus] register
+        pub async fn clear_alarm(&mut self) -> Result<(), Error<T::Error>> {
+            // Write bitmask to clear the alarm bit
+            self.write_reg(Register::MeasurementStatus, &[0b0000_0001])
+                .await
+        }
+
+        /// Configure when the interrupt pin is activated
+        pub async fn set_interrupt_config(
+            &mut self,
+            config: InterruptConfig,
+        ) -> Result<(), Error<T::Error>> {
+            let config: u8 = config.into();
+
+            #[cfg(feature = "defmt")]
+            defmt::info!("Setting interrupt config: {:b}", config);
+
+            self.write_reg(Register::InterruptConfig, &[config]).await
+        }
+
+        /// Get the current sensor [InterruptConfig]
+        pub async fn get_interrupt_config(&mut self) -> Result<InterruptConfig, Error<T::Error>> {
+            let val = self.read_reg_u8(Register::InterruptConfig).await?;
+            val.try_into()
+                .map_err(|_| Error::Response(ResponseError::InvalidRegisterValue))
+        }
+
+        /// Set the threshold for the alarm status bit or interrupt (if enabled)
+        pub async fn set_alarm_threshold(
+            &mut self,
+            threshold: AlarmThresholdPpm,
+        ) -> Result<(), Error<T::Error>> {
+            let threshold = threshold.get().to_be_bytes();
+
+            self.write_reg(Register::AlarmThreshold, &threshold).await
+        }
+
+        /// Get the threshold for the alarm status bit or interrupt
+        pub async fn get_alarm_threshold(&mut self) -> Result<AlarmThresholdPpm, Error<T::Error>> {
+            let threshold = self.read_reg_i16(Register::AlarmThreshold).await?;
+            AlarmThresholdPpm::new(threshold)
+                .map_err(|_| Error::Response(ResponseError::InvalidRegisterValue))
+        }
+
+        /// Set the ambient pressure used for pressure compensation
+        pub async fn set_pressure_compensation(
+            &mut self,
+            pressure: PressureHpa,
+        ) -> Result<(), Error<T::Error>> {
+            let pressure = pressure.get().to_be_bytes();
+
+            self.write_reg(Register::PressureReference, &pressure).await
+        }
+
+        /// Set the pressure compensation from the altitude of the sensor in meters
+        ///
+        /// The pressure is computed with [PressureHpa::from_altitude()], using the sea level
+        /// pressure `sea_level_hpa` or [STANDARD_SEA_LEVEL_HPA] if `None`. Returns the pressure
+        /// written, or [Error::OutOfRange] if it is outside of the range of the sensor.
+        pub async fn set_pressure_compensation_from_altitude(
+            &mut self,
+            altitude_m: f32,
+            sea_level_hpa: Option<f32>,
+        ) -> Result<PressureHpa, Error<T::Error>> {
+            let sea_level_hpa = sea_level_hpa.unwrap_or(STANDARD_SEA_LEVEL_HPA);
+            let pressure = PressureHpa::from_altitude(altitude_m, sea_level_hpa)
+                .map_err(|_| Error::OutOfRange)?;
+            self.set_pressure_compensation(pressure).await?;
+            Ok(pressure)
+        }
+
+        /// Get the ambient pressure used for pressure compensation
+        pub async fn get_pressure_compensation(&mut self) -> Result<PressureHpa, Error<T::Error>> {
+            let pressure = self.read_reg_u16(Register::PressureReference).await?;
+            PressureHpa::new(pressure)
+                .map_err(|_| Error::Response(ResponseError::InvalidRegisterValue))
+        }
+
+        /// Convert a CO2 reading to a mass concentration at `temperature_centi_c` (in 0.01 °C)
+        ///
+        /// Uses the pressure programmed for pressure compensation, see
+        /// [MassConcentration::from_ppm()] to supply a pressure. Fails with [Error::OutOfRange]
+        /// for temperatures below
+        /// [MIN_TEMPERATURE_CENTI_C](crate::concentration::MIN_TEMPERATURE_CENTI_C).
+        pub async fn co2_mass_concentration(
+            &mut self,
+            co2_ppm: i16,
+            temperature_centi_c: i16,
+        ) -> Result<MassConcentration, Error<T::Error>> {
+            let pressure = self.get_pressure_compensation().await?;
+            MassConcentration::from_ppm(co2_ppm, temperature_centi_c, pressure)
+                .map_err(|_| Error::OutOfRange)
+        }
+
+        /// Set the reference CO2 concentration for baseline offset compensation
+        ///
+        /// Used by the automatic and the forced baseline offset compensation, see
+        /// [BaselineOffsetCompensation]. The value is read back afterwards, a mismatch results in
+        /// [ResponseError::ReadBackMismatch].
+        pub async fn set_calibration_reference(
+            &mut self,
+            reference: CalibrationPpm,
+        ) -> Result<(), Error<T::Error>> {
+            self.write_reg(
+                Register::CalibrationReference,
+                &reference.get().to_be_bytes(),
+            )
+            .await?;
+
+            if self.get_calibration_reference().await? != reference {
+                return Err(Error::Response(ResponseError::ReadBackMismatch));
+            }
+            Ok(())
+        }
+
+        /// Get the reference CO2 concentration for baseline offset compensation
+        pub async fn get_calibration_reference(
+            &mut self,
+        ) -> Result<CalibrationPpm, Error<T::Error>> {
+            let reference = self.read_reg_i16(Register::CalibrationReference).await?;
+            CalibrationPpm::new(reference)
+                .map_err(|_| Error::Response(ResponseError::InvalidRegisterValue))
+        }
+
+        /// Set the Automatic Baseline Offset Compensation Reference
+        #[deprecated(note = "use `set_calibration_reference()`")]
+        pub async fn set_aboc(&mut self, aboc: CalibrationPpm) -> Result<(), Error<T::Error>> {
+            self.set_calibration_reference(aboc).await
+        }
+
+        /// Get the Automatic Baseline Offset Compensation Reference
+        #[deprecated(note = "use `get_calibration_reference()`")]
+        pub async fn get_aboc(&mut self) -> Result<CalibrationPpm, Error<T::Error>> {
+            self.get_calibration_reference().await
+        }
+
+        /// Perform a write-then-read to the scratch pad register and return the read back value.
+        pub async fn test_write_read(&mut self, val: u8) -> Result<u8, Error<T::Error>> {
+            self.write_reg(Register::ScratchPad, &[val]).await?;
+
+            self.read_reg_u8(Register::ScratchPad).await
+        }
+
+        /// Send a [SoftReset] event to the sensor
+        pub async fn soft_reset(&mut self, reset: SoftReset) -> Result<(), Error<T::Error>> {
+            let iir_filter = match reset {
+                SoftReset::EnableStepwiseReaciveIirFilter => Some(true),
+                SoftReset::DisableStepwiseReractiveIirFilter => Some(false),
+                _ => self.iir_filter,
+            };
+            self.write_reg(Register::SensorReset, &[reset.into()])
+                .await?;
+            self.iir_filter = iir_filter;
+            Ok(())
+        }
+
+        /// Whether the sensor's stepwise reactive IIR filter is enabled
+        ///
+        /// The state cannot be read from the sensor, so it is only known once it was set with
+        /// [Self::soft_reset()]. `None` before. The filter is enabled after power-up.
+        pub fn iir_filter_enabled(&self) -> Option<bool> {
+            self.iir_filter
+        }
+
+        /// Length of val must be 1 or 2. The sensor only has 1 or 2 byte registers
+        async fn write_reg(&mut self, reg: Register, val: &[u8]) -> Result<(), Error<T::Error>> {
+            self.finish_pending().await?;
+            self.interface
+                .write_reg(reg, val)
+                .await
+                .map_err(Error::Interface)
+        }
+
+        async fn read_reg(&mut self, reg: Register, buf: &mut [u8]) -> Result<(), Error<T::Error>> {
+            self.finish_pending().await?;
+            self.interface
+                .read_reg(reg, buf)
+                .await
+                .map_err(Error::Interface)
+        }
+
+        async fn read_reg_u8(&mut self, register: Register) -> Result<u8, Error<T::Error>> {
+            let mut result = [0u8; 1];
+            self.read_reg(register, &mut result[..]).await?;
+            Ok(result[0])
+        }
+
+        async fn read_reg_u16(&mut self, register: Register) -> Result<u16, Error<T::Error>> {
+            let mut bytes = [0u8; 2];
+            self.read_reg(register, &mut bytes[..]).await?;
+            Ok(u16::from_be_bytes(bytes))
+        }
+
+        async fn read_reg_i16(&mut self, register: Register) -> Result<i16, Error<T::Error>> {
+            let mut bytes = [0u8; 2];
+            self.read_reg(register, &mut bytes[..]).await?;
+            Ok(i16::from_be_bytes(bytes))
+        }
+
+        /// Enter idle mode and enable the IIR filter if requested by [Self::idle_pending] and
+        /// [Self::iir_filter_pending].
+        ///
+        /// Accesses the interface directly, as async functions cannot recurse into
+        /// [Self::write_reg()].
+        async fn finish_pending(&mut self) -> Result<(), Error<T::Error>> {
+            if self.idle_pending {
+                let mut mode = [0u8; 1];
+                self.interface
+                    .read_reg(Register::MeasurementMode, &mut mode)
+                    .await
+                    .map_err(Error::Interface)?;
+                let mut mode = MeasurementMode::from(mode[0]);
+                mode.operating_mode = OperatingMode::Idle;
+                self.interface
+                    .write_reg(Register::MeasurementMode, &[mode.into()])
+                    .await
+                    .map_err(Error::Interface)?;
+                self.idle_pending = false;
+            }
+            if self.iir_filter_pending {
+                self.interface
+                    .write_reg(
+                        Register::SensorReset,
+                        &[SoftReset::EnableStepwiseReaciveIirFilter.into()],
+                    )
+                    .await
+                    .map_err(Error::Interface)?;
+                self.iir_filter = Some(true);
+                self.iir_filter_pending = false;
+            }
+            Ok(())
+        }
+    }
+}
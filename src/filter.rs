@@ -0,0 +1,289 @@
+//! Filters for smoothing CO2 readings
+//!
+//! All filters implement [Filter] and keep their history in fixed-size buffers, so they need
+//! no allocation. Filters are combined with [Filter::then()] and can be attached to
+//! [ContinuousReadings](crate::ContinuousReadings::with_filter()).
+//!
+//! ```
+//! use pas_co2_rs::filter::{Exponential, Filter, Median};
+//!
+//! // Remove single outliers, then smooth
+//! let mut filter = Median::<3>::new().then(Exponential::new(0.5).unwrap());
+//! assert_eq!(filter.update(400), 400);
+//! assert_eq!(filter.update(400), 400);
+//! assert_eq!(filter.update(2000), 400);
+//! assert_eq!(filter.update(440), 420);
+//! ```
+//!
+//! The sensor has a stepwise reactive IIR filter of its own, see
+//! [SoftReset::EnableStepwiseReaciveIirFilter](crate::regs::SoftReset::EnableStepwiseReaciveIirFilter).
+//! Filtering its output again mostly adds delay, which is why
+//! [ContinuousReadings::with_filter()](crate::ContinuousReadings::with_filter()) disables it.
+
+use crate::regs::OutOfRangeError;
+
+/// Filter for a sequence of CO2 readings in PPM
+pub trait Filter {
+    /// Feed the next reading and return the filtered value
+    fn update(&mut self, co2_ppm: i16) -> i16;
+
+    /// Forget all previous readings
+    fn reset(&mut self);
+
+    /// Pass the output of this filter through `next`
+    fn then<F: Filter>(self, next: F) -> Chain<Self, F>
+    where
+        Self: Sized,
+    {
+        Chain { first: self, next }
+    }
+}
+
+/// Two filters in series, see [Filter::then()]
+#[derive(Clone, Debug)]
+pub struct Chain<A, B> {
+    first: A,
+    next: B,
+}
+
+impl<A: Filter, B: Filter> Filter for Chain<A, B> {
+    fn update(&mut self, co2_ppm: i16) -> i16 {
+        self.next.update(self.first.update(co2_ppm))
+    }
+
+    fn reset(&mut self) {
+        self.first.reset();
+        self.next.reset();
+    }
+}
+
+/// The last `N` readings
+#[derive(Clone, Debug)]
+struct Window<const N: usize> {
+    readings: [i16; N],
+    /// Number of valid readings, at most `N`
+    len: usize,
+    /// Index the next reading is written to
+    next: usize,
+}
+
+impl<const N: usize> Window<N> {
+    fn new() -> Self {
+        const { assert!(N > 0, "the window must hold at least one reading") };
+        Self {
+            readings: [0; N],
+            len: 0,
+            next: 0,
+        }
+    }
+
+    fn push(&mut self, co2_ppm: i16) {
+        self.readings[self.next] = co2_ppm;
+        self.next = (self.next + 1) % N;
+        self.len = (self.len + 1).min(N);
+    }
+
+    fn readings(&self) -> &[i16] {
+        // Until the window is full, the readings are at the start
+        &self.readings[..self.len]
+    }
+
+    fn clear(&mut self) {
+        self.len = 0;
+        self.next = 0;
+    }
+}
+
+/// Integer division of `sum` by `count`, rounded half up
+fn div_round(sum: i32, count: i32) -> i16 {
+    (2 * sum + count).div_euclid(2 * count) as i16
+}
+
+/// Average of the last `N` readings
+///
+/// Until `N` readings arrived, the average of all readings so far.
+#[derive(Clone, Debug)]
+pub struct MovingAverage<const N: usize> {
+    window: Window<N>,
+}
+
+impl<const N: usize> MovingAverage<N> {
+    /// `N` must not be 0, which is checked at compile time:
+    ///
+    /// ```compile_fail
+    /// let filter = pas_co2_rs::filter::MovingAverage::<0>::new();
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            window: Window::new(),
+        }
+    }
+}
+
+impl<const N: usize> Default for MovingAverage<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Filter for MovingAverage<N> {
+    fn update(&mut self, co2_ppm: i16) -> i16 {
+        self.window.push(co2_ppm);
+        let readings = self.window.readings();
+        let sum = readings.iter().map(|&r| i32::from(r)).sum();
+        div_round(sum, readings.len() as i32)
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+    }
+}
+
+/// Median of the last `N` readings, removes outliers
+///
+/// Until `N` readings arrived, the median of all readings so far. For an even number of
+/// readings, the average of the two middle ones.
+#[derive(Clone, Debug)]
+pub struct Median<const N: usize> {
+    window: Window<N>,
+}
+
+impl<const N: usize> Median<N> {
+    /// `N` must not be 0, which is checked at compile time.
+    pub fn new() -> Self {
+        Self {
+            window: Window::new(),
+        }
+    }
+}
+
+impl<const N: usize> Default for Median<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Filter for Median<N> {
+    fn update(&mut self, co2_ppm: i16) -> i16 {
+        self.window.push(co2_ppm);
+
+        let mut sorted = [0; N];
+        let sorted = &mut sorted[..self.window.len];
+        sorted.copy_from_slice(self.window.readings());
+        sorted.sort_unstable();
+
+        let middle = sorted.len() / 2;
+        if sorted.len() % 2 == 1 {
+            sorted[middle]
+        } else {
+            div_round(i32::from(sorted[middle - 1]) + i32::from(sorted[middle]), 2)
+        }
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+    }
+}
+
+/// Exponential moving average
+///
+/// Each output is `alpha * reading + (1 - alpha) * previous output`, starting with the first
+/// reading. Smaller values of `alpha` smooth more.
+#[derive(Clone, Debug)]
+pub struct Exponential {
+    alpha: f32,
+    value: Option<f32>,
+}
+
+impl Exponential {
+    /// Fails unless `0 < alpha <= 1`.
+    pub fn new(alpha: f32) -> Result<Self, OutOfRangeError> {
+        if !(alpha > 0.0 && alpha <= 1.0) {
+            return Err(OutOfRangeError);
+        }
+        Ok(Self { alpha, value: None })
+    }
+}
+
+impl Filter for Exponential {
+    fn update(&mut self, co2_ppm: i16) -> i16 {
+        let reading = f32::from(co2_ppm);
+        let value = match self.value {
+            Some(previous) => previous + self.alpha * (reading - previous),
+            None => reading,
+        };
+        self.value = Some(value);
+        libm::roundf(value) as i16
+    }
+
+    fn reset(&mut self) {
+        self.value = None;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn apply(filter: &mut impl Filter, readings: &[i16]) -> [i16; 6] {
+        let mut out = [0; 6];
+        for (out, &reading) in out.iter_mut().zip(readings) {
+            *out = filter.update(reading);
+        }
+        out
+    }
+
+    #[test]
+    fn test_moving_average() {
+        let mut filter = MovingAverage::<3>::new();
+        assert_eq!(
+            apply(&mut filter, &[400, 401, 410, 430, 430, 430]),
+            [400, 401, 404, 414, 423, 430]
+        );
+
+        filter.reset();
+        assert_eq!(filter.update(-5), -5);
+        assert_eq!(filter.update(-6), -5);
+    }
+
+    #[test]
+    fn test_median() {
+        let mut filter = Median::<3>::new();
+        assert_eq!(
+            apply(&mut filter, &[400, 2000, 410, 405, -100, 420]),
+            [400, 1200, 410, 410, 405, 405]
+        );
+
+        filter.reset();
+        assert_eq!(filter.update(600), 600);
+    }
+
+    #[test]
+    fn test_exponential() {
+        let mut filter = Exponential::new(0.25).unwrap();
+        assert_eq!(
+            apply(&mut filter, &[400, 800, 800, 800, 800, 800]),
+            [400, 500, 575, 631, 673, 705]
+        );
+
+        let mut unfiltered = Exponential::new(1.0).unwrap();
+        assert_eq!(unfiltered.update(400), 400);
+        assert_eq!(unfiltered.update(800), 800);
+
+        for alpha in [0.0, -0.5, 1.01, f32::NAN, f32::INFINITY] {
+            assert_eq!(Exponential::new(alpha).err(), Some(OutOfRangeError));
+        }
+    }
+
+    #[test]
+    fn test_chain() {
+        let mut filter = Median::<3>::new().then(MovingAverage::<2>::new());
+        assert_eq!(
+            apply(&mut filter, &[400, 400, 5000, 400, 420, 420]),
+            [400, 400, 400, 400, 410, 420]
+        );
+
+        filter.reset();
+        assert_eq!(filter.update(1000), 1000);
+    }
+}
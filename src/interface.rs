@@ -0,0 +1,73 @@
+// See driver.rs on how the async and blocking variants are generated.
+#[maybe_async_cfg::maybe(sync(self = "blocking"), async(self = "asynchronous"))]
+pub mod interface {
+    #[maybe_async_cfg::only_if(sync)]
+    use embedded_hal::i2c::{Error as ehal_i2c_error, ErrorKind, I2c, SevenBitAddress};
+    #[maybe_async_cfg::only_if(async)]
+    use embedded_hal_async::i2c::{Error as ehal_i2c_error, ErrorKind, I2c, SevenBitAddress};
+
+    use crate::regs::Register;
+    use crate::ADDRESS;
+
+    /// Serial interface the sensor is connected with
+    ///
+    /// The sensor supports I2C ([I2cInterface]) and UART (`UartInterface`, requires the `uart`
+    /// feature), selected by its PSEL pin.
+    #[allow(async_fn_in_trait)]
+    pub trait Interface {
+        /// Error type of the underlying bus
+        type Error;
+
+        /// Write `val` to the register starting at `reg`.
+        ///
+        /// Length of val must be 1 or 2. The sensor only has 1 or 2 byte registers
+        async fn write_reg(&mut self, reg: Register, val: &[u8]) -> Result<(), Self::Error>;
+
+        /// Read `buf.len()` bytes from the register starting at `reg`.
+        async fn read_reg(&mut self, reg: Register, buf: &mut [u8]) -> Result<(), Self::Error>;
+
+        /// Whether `error` means that the sensor did not acknowledge the transfer,
+        /// which it does e.g. while it is busy with a measurement.
+        fn is_nack(error: &Self::Error) -> bool;
+    }
+
+    /// I2C [Interface] of the sensor at [ADDRESS]
+    pub struct I2cInterface<I2C> {
+        i2c: I2C,
+    }
+
+    impl<I2C> I2cInterface<I2C>
+    where
+        I2C: I2c<SevenBitAddress>,
+    {
+        /// Create a new I2C interface
+        pub fn new(i2c: I2C) -> Self {
+            Self { i2c }
+        }
+    }
+
+    impl<I2C> Interface for I2cInterface<I2C>
+    where
+        I2C: I2c<SevenBitAddress>,
+    {
+        type Error = I2C::Error;
+
+        async fn write_reg(&mut self, reg: Register, val: &[u8]) -> Result<(), Self::Error> {
+            assert!(val.len() <= 2);
+            assert!(!val.is_empty());
+            match val.len() {
+                1 => self.i2c.write(ADDRESS, &[reg.into(), val[0]]).await,
+                2 => self.i2c.write(ADDRESS, &[reg.into(), val[0], val[1]]).await,
+                _ => panic!("Invalid length for write_reg"),
+            }
+        }
+
+        async fn read_reg(&mut self, reg: Register, buf: &mut [u8]) -> Result<(), Self::Error> {
+            self.i2c.write_read(ADDRESS, &[reg.into()], buf).await
+        }
+
+        fn is_nack(error: &Self::Error) -> bool {
+            matches!(error.kind(), ErrorKind::NoAcknowledge(_))
+        }
+    }
+}
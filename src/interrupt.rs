@@ -0,0 +1,85 @@
+// See driver.rs on how the async and blocking variants are generated.
+#[maybe_async_cfg::maybe(sync(self = "blocking"), async(self = "asynchronous"))]
+pub mod interrupt {
+    #[maybe_async_cfg::only_if(async)]
+    use crate::driver::asynchronous::PasCo2;
+    #[maybe_async_cfg::only_if(sync)]
+    use crate::driver::blocking::PasCo2;
+    #[maybe_async_cfg::only_if(async)]
+    use crate::interface::asynchronous::Interface;
+    #[maybe_async_cfg::only_if(sync)]
+    use crate::interface::blocking::Interface;
+    use embedded_hal::digital::Error as _;
+    #[maybe_async_cfg::only_if(sync)]
+    use embedded_hal::digital::InputPin as IntPin;
+    #[maybe_async_cfg::only_if(async)]
+    use embedded_hal_async::digital::Wait as IntPin;
+
+    use crate::regs::IntFunctionConfig;
+    use crate::Error;
+
+    impl<T> PasCo2<T>
+    where
+        T: Interface,
+    {
+        /// Wait until the INT pin signals that new data is available
+        ///
+        /// The INT pin must be configured for [IntFunctionConfig::DataReady], otherwise
+        /// [Error::IntPinNotConfigured] is returned. Clears the int active bit afterwards.
+        ///
+        /// The async driver awaits the pin's edge, the blocking driver busy-waits on its level.
+        /// Neither accesses the sensor while waiting.
+        pub async fn wait_for_data_ready(
+            &mut self,
+            pin: &mut impl IntPin,
+        ) -> Result<(), Error<T::Error>> {
+            self.wait_for_int_pin(pin, IntFunctionConfig::DataReady)
+                .await
+        }
+
+        /// Wait until the INT pin signals an alarm threshold violation
+        ///
+        /// The INT pin must be configured for [IntFunctionConfig::Alarm], otherwise
+        /// [Error::IntPinNotConfigured] is returned. Clears the int active bit afterwards.
+        ///
+        /// See [Self::wait_for_data_ready()] on how the pin is awaited.
+        pub async fn wait_for_alarm(
+            &mut self,
+            pin: &mut impl IntPin,
+        ) -> Result<(), Error<T::Error>> {
+            self.wait_for_int_pin(pin, IntFunctionConfig::Alarm).await
+        }
+
+        async fn wait_for_int_pin(
+            &mut self,
+            pin: &mut impl IntPin,
+            function: IntFunctionConfig,
+        ) -> Result<(), Error<T::Error>> {
+            let config = self.get_interrupt_config().await?;
+            if config.int_function_config != function {
+                return Err(Error::IntPinNotConfigured);
+            }
+
+            wait_for_level(pin, config.int_pin_active_high)
+                .await
+                .map_err(|e| Error::Pin(e.kind()))?;
+
+            self.clear_int_active().await
+        }
+    }
+
+    #[maybe_async_cfg::only_if(async)]
+    async fn wait_for_level<P: IntPin>(pin: &mut P, high: bool) -> Result<(), P::Error> {
+        if high {
+            pin.wait_for_high().await
+        } else {
+            pin.wait_for_low().await
+        }
+    }
+
+    #[maybe_async_cfg::only_if(sync)]
+    fn wait_for_level<P: IntPin>(pin: &mut P, high: bool) -> Result<(), P::Error> {
+        while pin.is_high()? != high {}
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+//! Sensors behind a TCA9548A I2C multiplexer
+//!
+//! All sensors share the fixed [ADDRESS](crate::ADDRESS), so several of them on one bus need a
+//! multiplexer. [Tca9548a] owns the bus and hands out a [MuxChannel] per sensor, which
+//! implements the (async and blocking) `I2c` traits and can be passed to
+//! [PasCo2::new](crate::PasCo2::new). Each transaction on a [MuxChannel] first selects its
+//! channel, unless already selected, while holding the bus, so no other channel can interfere.
+//! Async transactions on different channels wait for each other, so the sensors can be driven
+//! concurrently, e.g. from several tasks on one executor.
+//!
+//! ```
+//! # use pas_co2_rs::simulator::Simulator;
+//! use pas_co2_rs::mux::{Tca9548a, TCA9548A_ADDRESS};
+//! use pas_co2_rs::PasCo2;
+//!
+//! # let i2c = Simulator::new();
+//! let mux = Tca9548a::new(i2c, TCA9548A_ADDRESS);
+//! let sensors: [_; 4] = core::array::from_fn(|channel| PasCo2::new(mux.channel(channel as u8)));
+//! ```
+
+use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+use embassy_sync::mutex::Mutex;
+use embedded_hal::i2c::{Error, ErrorKind, ErrorType, Operation, SevenBitAddress};
+
+/// Default I2C address of the TCA9548A (A2..A0 low)
+pub const TCA9548A_ADDRESS: u8 = 0x70;
+
+/// Number of channels of the TCA9548A
+pub const CHANNEL_COUNT: u8 = 8;
+
+/// Error of a [MuxChannel]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone)]
+pub enum MuxError<E> {
+    /// Error on the underlying bus, while selecting the channel or during the transaction
+    I2c(E),
+
+    /// A blocking transaction was attempted while an async transaction is in progress
+    ///
+    /// Async transactions wait for the bus instead.
+    Busy,
+}
+
+impl<E: Error> Error for MuxError<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            MuxError::I2c(e) => e.kind(),
+            MuxError::Busy => ErrorKind::Other,
+        }
+    }
+}
+
+/// TCA9548A I2C multiplexer, see the [module documentation](self)
+pub struct Tca9548a<I2C> {
+    bus: Mutex<NoopRawMutex, Bus<I2C>>,
+}
+
+struct Bus<I2C> {
+    i2c: I2C,
+    address: u8,
+    /// Channel selected last, `None` if unknown
+    selected: Option<u8>,
+}
+
+/// Channel of a [Tca9548a], see [Tca9548a::channel()]
+pub struct MuxChannel<'a, I2C> {
+    bus: &'a Mutex<NoopRawMutex, Bus<I2C>>,
+    channel: u8,
+}
+
+impl<I2C> Tca9548a<I2C> {
+    /// Create a multiplexer at `address` on the bus `i2c`
+    pub fn new(i2c: I2C, address: u8) -> Self {
+        Self {
+            bus: Mutex::new(Bus {
+                i2c,
+                address,
+                selected: None,
+            }),
+        }
+    }
+
+    /// Obtain the I2C bus behind `channel`
+    ///
+    /// Panics if `channel` is not below [CHANNEL_COUNT].
+    pub fn channel(&self, channel: u8) -> MuxChannel<'_, I2C> {
+        assert!(channel < CHANNEL_COUNT);
+        MuxChannel {
+            bus: &self.bus,
+            channel,
+        }
+    }
+
+    /// Release the underlying bus
+    pub fn release(self) -> I2C {
+        self.bus.into_inner().i2c
+    }
+}
+
+impl<I2C: ErrorType> ErrorType for MuxChannel<'_, I2C> {
+    type Error = MuxError<I2C::Error>;
+}
+
+impl<I2C> embedded_hal::i2c::I2c<SevenBitAddress> for MuxChannel<'_, I2C>
+where
+    I2C: embedded_hal::i2c::I2c<SevenBitAddress>,
+{
+    fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let mut bus = self.bus.try_lock().map_err(|_| MuxError::Busy)?;
+        if bus.selected != Some(self.channel) {
+            bus.selected = None;
+            let (mux_address, mask) = (bus.address, 1 << self.channel);
+            bus.i2c.write(mux_address, &[mask]).map_err(MuxError::I2c)?;
+            bus.selected = Some(self.channel);
+        }
+        bus.i2c
+            .transaction(address, operations)
+            .map_err(MuxError::I2c)
+    }
+}
+
+impl<I2C> embedded_hal_async::i2c::I2c<SevenBitAddress> for MuxChannel<'_, I2C>
+where
+    I2C: embedded_hal_async::i2c::I2c<SevenBitAddress>,
+{
+    // Holding the bus across the awaits makes selection and transaction atomic
+    async fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let mut bus = self.bus.lock().await;
+        if bus.selected != Some(self.channel) {
+            bus.selected = None;
+            let (mux_address, mask) = (bus.address, 1 << self.channel);
+            bus.i2c
+                .write(mux_address, &[mask])
+                .await
+                .map_err(MuxError::I2c)?;
+            bus.selected = Some(self.channel);
+        }
+        bus.i2c
+            .transaction(address, operations)
+            .await
+            .map_err(MuxError::I2c)
+    }
+}
@@ -0,0 +1,124 @@
+use crate::Error;
+
+/// Value written to and read back from the scratch pad register during [PasCo2::init()](crate::PasCo2::init())
+const SCRATCH_PAD_TEST_VALUE: u8 = 0b1010_0101;
+
+/// Step of [PasCo2::init()](crate::PasCo2::init())
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum InitStep {
+    /// Waiting for the sensor ready bit
+    WaitReady,
+    /// Clearing the temperature, voltage and communication errors
+    ClearErrors,
+    /// Writing and reading back the scratch pad register
+    ScratchPad,
+}
+
+/// Error of [PasCo2::init()](crate::PasCo2::init()), naming the step that failed
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone)]
+pub struct InitError<T> {
+    /// Step that failed
+    pub step: InitStep,
+    /// Cause of the failure
+    ///
+    /// [Error::Timeout] if the sensor did not become ready in time,
+    /// [ResponseError::ReadBackMismatch](crate::ResponseError::ReadBackMismatch) if the scratch
+    /// pad did not read back the written value.
+    pub error: Error<T>,
+}
+
+// See driver.rs on how the async and blocking variants are generated.
+#[maybe_async_cfg::maybe(sync(self = "blocking"), async(self = "asynchronous"))]
+pub mod power_up {
+    #[maybe_async_cfg::only_if(async)]
+    use crate::driver::asynchronous::PasCo2;
+    #[maybe_async_cfg::only_if(sync)]
+    use crate::driver::blocking::PasCo2;
+    #[maybe_async_cfg::only_if(async)]
+    use crate::interface::asynchronous::Interface;
+    #[maybe_async_cfg::only_if(sync)]
+    use crate::interface::blocking::Interface;
+    #[maybe_async_cfg::only_if(sync)]
+    use embedded_hal::delay::DelayNs;
+    #[maybe_async_cfg::only_if(async)]
+    use embedded_hal_async::delay::DelayNs;
+
+    use super::{InitError, InitStep, SCRATCH_PAD_TEST_VALUE};
+    use crate::{Error, ResponseError, STARTUP_POLL_INTERVAL_MS};
+
+    impl<T> PasCo2<T>
+    where
+        T: Interface,
+    {
+        /// Bring up the sensor after power-on
+        ///
+        /// Polls the [Status](crate::regs::Status) every [STARTUP_POLL_INTERVAL_MS] until the
+        /// sensor is ready, as writes are lost before. Then clears stale temperature, voltage and
+        /// communication errors and checks communication via the scratch pad register
+        /// (see [Self::test_write_read()]).
+        ///
+        /// Returns an [InitError] naming the step that failed. NACKs while waiting for the
+        /// sensor are not treated as an error.
+        pub async fn init(
+            &mut self,
+            mut delay: impl DelayNs,
+            timeout_ms: u32,
+        ) -> Result<(), InitError<T::Error>> {
+            self.wait_until_ready(&mut delay, timeout_ms)
+                .await
+                .map_err(|error| InitError {
+                    step: InitStep::WaitReady,
+                    error,
+                })?;
+
+            self.clear_status().await.map_err(|error| InitError {
+                step: InitStep::ClearErrors,
+                error,
+            })?;
+
+            let read = self
+                .test_write_read(SCRATCH_PAD_TEST_VALUE)
+                .await
+                .map_err(|error| InitError {
+                    step: InitStep::ScratchPad,
+                    error,
+                })?;
+            if read != SCRATCH_PAD_TEST_VALUE {
+                return Err(InitError {
+                    step: InitStep::ScratchPad,
+                    error: Error::Response(ResponseError::ReadBackMismatch),
+                });
+            }
+
+            Ok(())
+        }
+
+        async fn wait_until_ready(
+            &mut self,
+            delay: &mut impl DelayNs,
+            timeout_ms: u32,
+        ) -> Result<(), Error<T::Error>> {
+            let mut waited_ms = 0;
+            loop {
+                match self.get_status().await {
+                    Ok(status) if status.ready => return Ok(()),
+                    Ok(_) => (),
+                    Err(Error::Interface(e)) if T::is_nack(&e) => {
+                        #[cfg(feature = "defmt")]
+                        defmt::warn!("Got Nack instead of Status");
+                    }
+                    Err(e) => return Err(e),
+                }
+
+                if waited_ms >= timeout_ms {
+                    return Err(Error::Timeout);
+                }
+                let step = STARTUP_POLL_INTERVAL_MS.min(timeout_ms - waited_ms);
+                delay.delay_ms(step).await;
+                waited_ms += step;
+            }
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use crate::Error;
+
+/// Error of [PressureCompensated](crate::PressureCompensated)
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone)]
+pub enum PressureError<T, P> {
+    /// Error of the driver
+    Driver(Error<T>),
+    /// Error of the [PressureSource](crate::PressureSource), no measurement was started
+    Source(P),
+}
+
+impl<T, P> From<Error<T>> for PressureError<T, P> {
+    fn from(error: Error<T>) -> Self {
+        Self::Driver(error)
+    }
+}
+
+// See driver.rs on how the async and blocking variants are generated.
+#[maybe_async_cfg::maybe(sync(self = "blocking"), async(self = "asynchronous"))]
+pub mod pressure_source {
+    #[maybe_async_cfg::only_if(async)]
+    use crate::continuous::asynchronous::ContinuousReadings;
+    #[maybe_async_cfg::only_if(sync)]
+    use crate::continuous::blocking::ContinuousReadings;
+    #[maybe_async_cfg::only_if(async)]
+    use crate::driver::asynchronous::PasCo2;
+    #[maybe_async_cfg::only_if(sync)]
+    use crate::driver::blocking::PasCo2;
+    #[maybe_async_cfg::only_if(async)]
+    use crate::interface::asynchronous::Interface;
+    #[maybe_async_cfg::only_if(sync)]
+    use crate::interface::blocking::Interface;
+    #[maybe_async_cfg::only_if(sync)]
+    use embedded_hal::delay::DelayNs;
+    #[maybe_async_cfg::only_if(async)]
+    use embedded_hal_async::delay::DelayNs;
+
+    use super::PressureError;
+    use crate::regs::{MeasurementPeriod, PressureHpa};
+    use crate::Error;
+
+    /// Source of the ambient pressure, e.g. a barometer driver
+    #[allow(async_fn_in_trait)]
+    pub trait PressureSource {
+        /// Error of the source
+        type Error;
+
+        /// Read the current ambient pressure in hPa
+        ///
+        /// The value does not need to be within [PressureHpa::MIN] and [PressureHpa::MAX], it
+        /// is clamped before it is written to the sensor.
+        async fn pressure_hpa(&mut self) -> Result<u16, Self::Error>;
+    }
+
+    /// Driver that updates the pressure compensation from a [PressureSource] before each
+    /// measurement, see [PasCo2::with_pressure_source()]
+    pub struct PressureCompensated<T: Interface, S: PressureSource> {
+        driver: PasCo2<T>,
+        compensation: Compensation<S>,
+    }
+
+    /// Readings in continuous mode with pressure compensation, see
+    /// [PressureCompensated::start_continuous()]
+    pub struct PressureCompensatedReadings<'a, T: Interface, S: PressureSource, D: DelayNs> {
+        readings: ContinuousReadings<'a, T, D>,
+        compensation: &'a mut Compensation<S>,
+    }
+
+    type CompensatedResult<V, T, S> =
+        Result<V, PressureError<<T as Interface>::Error, <S as PressureSource>::Error>>;
+
+    struct Compensation<S> {
+        source: S,
+        max_drift_hpa: u16,
+        /// Pressure reference written last, `None` if unknown
+        programmed: Option<PressureHpa>,
+    }
+
+    impl<S> Compensation<S>
+    where
+        S: PressureSource,
+    {
+        /// See [PressureCompensated::refresh_pressure()]
+        async fn refresh<T: Interface>(
+            &mut self,
+            driver: &mut PasCo2<T>,
+        ) -> Result<PressureHpa, PressureError<T::Error, S::Error>> {
+            let hpa = self
+                .source
+                .pressure_hpa()
+                .await
+                .map_err(PressureError::Source)?;
+            let measured = PressureHpa::new_saturating(hpa);
+
+            match self.programmed {
+                Some(programmed)
+                    if programmed.get().abs_diff(measured.get()) <= self.max_drift_hpa =>
+                {
+                    Ok(programmed)
+                }
+                _ => {
+                    // Unknown until the write succeeded
+                    self.programmed = None;
+                    driver.set_pressure_compensation(measured).await?;
+                    self.programmed = Some(measured);
+                    Ok(measured)
+                }
+            }
+        }
+    }
+
+    impl<T> PasCo2<T>
+    where
+        T: Interface,
+    {
+        /// Compensate measurements with the pressure read from `source`
+        ///
+        /// Before each measurement started through the returned [PressureCompensated], the
+        /// pressure is read from `source`, clamped to the range of [PressureHpa] and written to
+        /// the sensor if it differs by more than `max_drift_hpa` from the value written last.
+        /// Use 0 to write every change.
+        pub fn with_pressure_source<S: PressureSource>(
+            self,
+            source: S,
+            max_drift_hpa: u16,
+        ) -> PressureCompensated<T, S> {
+            PressureCompensated {
+                driver: self,
+                compensation: Compensation {
+                    source,
+                    max_drift_hpa,
+                    programmed: None,
+                },
+            }
+        }
+    }
+
+    impl<T, S> PressureCompensated<T, S>
+    where
+        T: Interface,
+        S: PressureSource,
+    {
+        /// Read the pressure from the source and update the sensor's pressure compensation
+        /// if it drifted too far
+        ///
+        /// Returns the pressure the sensor compensates with.
+        pub async fn refresh_pressure(
+            &mut self,
+        ) -> Result<PressureHpa, PressureError<T::Error, S::Error>> {
+            self.compensation.refresh(&mut self.driver).await
+        }
+
+        /// Refresh the pressure compensation and start a single measurement, see
+        /// [PasCo2::start_measurement()]
+        pub async fn start_measurement(&mut self) -> Result<(), PressureError<T::Error, S::Error>> {
+            self.refresh_pressure().await?;
+            Ok(self.driver.start_measurement().await?)
+        }
+
+        /// Refresh the pressure compensation and perform a single measurement, see
+        /// [PasCo2::measure_single_shot()]
+        pub async fn measure_single_shot(
+            &mut self,
+            delay: impl DelayNs,
+            timeout_ms: u32,
+        ) -> Result<i16, PressureError<T::Error, S::Error>> {
+            self.refresh_pressure().await?;
+            Ok(self.driver.measure_single_shot(delay, timeout_ms).await?)
+        }
+
+        /// Refresh the pressure compensation and start measuring in continuous mode, see
+        /// [PasCo2::start_continuous()]
+        ///
+        /// The returned readings refresh the pressure compensation again before waiting for
+        /// each reading, so it applies to the sensor's next measurement.
+        pub async fn start_continuous<D: DelayNs>(
+            &mut self,
+            period: MeasurementPeriod,
+            delay: D,
+        ) -> CompensatedResult<PressureCompensatedReadings<'_, T, S, D>, T, S> {
+            self.refresh_pressure().await?;
+            let readings = self.driver.start_continuous(period, delay).await?;
+            Ok(PressureCompensatedReadings {
+                readings,
+                compensation: &mut self.compensation,
+            })
+        }
+
+        /// Access the driver, e.g. to read the measurement status
+        ///
+        /// As the driver may change the pressure compensation, it is written again on the
+        /// next refresh.
+        pub fn driver(&mut self) -> &mut PasCo2<T> {
+            self.compensation.programmed = None;
+            &mut self.driver
+        }
+
+        /// Release the driver and the pressure source
+        pub fn release(self) -> (PasCo2<T>, S) {
+            (self.driver, self.compensation.source)
+        }
+    }
+
+    impl<T, S, D> PressureCompensatedReadings<'_, T, S, D>
+    where
+        T: Interface,
+        S: PressureSource,
+        D: DelayNs,
+    {
+        /// Refresh the pressure compensation, then wait for the next reading and return it in
+        /// PPM, see [ContinuousReadings::next_reading()]
+        ///
+        /// The refreshed pressure applies to the measurement after the returned reading at the
+        /// latest, as the current one may already be in progress.
+        pub async fn next_reading(&mut self) -> Result<i16, PressureError<T::Error, S::Error>> {
+            self.compensation.refresh(self.readings.driver()).await?;
+            Ok(self.readings.next_reading().await?)
+        }
+
+        /// Stop the continuous measurements, see [ContinuousReadings::stop()]
+        pub async fn stop(self) -> Result<(), Error<T::Error>> {
+            self.readings.stop().await
+        }
+    }
+
+    #[maybe_async_cfg::only_if(sync)]
+    impl<T, S, D> Iterator for PressureCompensatedReadings<'_, T, S, D>
+    where
+        T: Interface,
+        S: PressureSource,
+        D: DelayNs,
+    {
+        type Item = Result<i16, PressureError<T::Error, S::Error>>;
+
+        /// Wait for the next reading, see [Self::next_reading()]. Never returns `None`.
+        fn next(&mut self) -> Option<Self::Item> {
+            Some(self.next_reading())
+        }
+    }
+}
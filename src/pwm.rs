@@ -0,0 +1,192 @@
+/// Period and high time of a PWM pulse in µs, as measured by a
+/// [PwmCapture](crate::PwmCapture)
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PwmPulse {
+    /// Time from one rising edge to the next
+    pub period_us: u32,
+    /// Time from the rising edge to the falling edge
+    pub high_us: u32,
+}
+
+/// The pulse is not a valid PWM output of the sensor, see [PwmConfig::decode()]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum InvalidPulse {
+    /// The period deviates from [PwmConfig::period_us] by more than the tolerance
+    Period,
+    /// The high time is 0 or not shorter than the period, i.e. the level is constant
+    HighTime,
+    /// The concentration exceeds the range of a reading, only possible with a
+    /// [PwmConfig::full_scale_ppm] above `i16::MAX`
+    Concentration,
+}
+
+/// Transfer function of the PWM output
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PwmConfig {
+    /// Nominal period of the PWM signal
+    pub period_us: u32,
+    /// Largest accepted deviation of a measured period from [Self::period_us]
+    pub period_tolerance_us: u32,
+    /// Concentration at a duty cycle of 100 %
+    pub full_scale_ppm: u16,
+}
+
+impl Default for PwmConfig {
+    /// 1 kHz with a tolerance of 10 % and a full scale of 10000 ppm
+    fn default() -> Self {
+        Self {
+            period_us: 1000,
+            period_tolerance_us: 100,
+            full_scale_ppm: 10_000,
+        }
+    }
+}
+
+impl PwmConfig {
+    /// Convert a pulse to the CO2 concentration in PPM
+    ///
+    /// The concentration is proportional to the duty cycle, rounded to whole PPM.
+    pub fn decode(&self, pulse: PwmPulse) -> Result<i16, InvalidPulse> {
+        if pulse.period_us.abs_diff(self.period_us) > self.period_tolerance_us
+            || pulse.period_us == 0
+        {
+            return Err(InvalidPulse::Period);
+        }
+        if pulse.high_us == 0 || pulse.high_us >= pulse.period_us {
+            return Err(InvalidPulse::HighTime);
+        }
+
+        let period = u64::from(pulse.period_us);
+        let ppm = (u64::from(pulse.high_us) * u64::from(self.full_scale_ppm) + period / 2) / period;
+        i16::try_from(ppm).map_err(|_| InvalidPulse::Concentration)
+    }
+}
+
+/// Error of a [PwmDecoder](crate::PwmDecoder)
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PwmError<E> {
+    /// Error of the [PwmCapture](crate::PwmCapture)
+    Capture(E),
+    /// The captured pulse is out of spec
+    InvalidPulse(InvalidPulse),
+}
+
+// See driver.rs on how the async and blocking variants are generated.
+#[maybe_async_cfg::maybe(sync(self = "blocking"), async(self = "asynchronous"))]
+pub mod pwm {
+    use super::{PwmConfig, PwmError, PwmPulse};
+
+    /// Measures pulses on the sensor's PWM pin, e.g. with a timer's input capture
+    #[allow(async_fn_in_trait)]
+    pub trait PwmCapture {
+        /// Error of the capture, e.g. a timeout if there are no edges
+        type Error;
+
+        /// Wait for and measure the next complete pulse
+        async fn capture(&mut self) -> Result<PwmPulse, Self::Error>;
+    }
+
+    /// Reads the CO2 concentration from the sensor's PWM output
+    ///
+    /// With [MeasurementMode::pwm_out_enable](crate::regs::MeasurementMode::pwm_out_enable)
+    /// set, the sensor encodes each reading in the duty cycle of its PWM pin, see
+    /// [PwmMode](crate::regs::PwmMode). Boards that only connect this pin can decode it from
+    /// the pulses measured by a [PwmCapture], e.g. a timer's input capture. Pulses that do not
+    /// match the [PwmConfig] are rejected.
+    pub struct PwmDecoder<C: PwmCapture> {
+        capture: C,
+        config: PwmConfig,
+    }
+
+    impl<C> PwmDecoder<C>
+    where
+        C: PwmCapture,
+    {
+        /// Decode pulses from `capture` with the transfer function `config`
+        pub fn new(capture: C, config: PwmConfig) -> Self {
+            Self { capture, config }
+        }
+
+        /// Capture the next pulse and return the CO2 concentration in PPM
+        pub async fn read_co2_ppm(&mut self) -> Result<i16, PwmError<C::Error>> {
+            let pulse = self.capture.capture().await.map_err(PwmError::Capture)?;
+            self.config.decode(pulse).map_err(PwmError::InvalidPulse)
+        }
+
+        /// Release the capture
+        pub fn release(self) -> C {
+            self.capture
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn pulse(period_us: u32, high_us: u32) -> PwmPulse {
+        PwmPulse { period_us, high_us }
+    }
+
+    #[test]
+    fn test_decode() {
+        let config = PwmConfig::default();
+        assert_eq!(config.decode(pulse(1000, 40)), Ok(400));
+        assert_eq!(config.decode(pulse(1000, 1)), Ok(10));
+        assert_eq!(config.decode(pulse(1000, 999)), Ok(9990));
+        // The duty cycle counts, not the absolute high time
+        assert_eq!(config.decode(pulse(1050, 63)), Ok(600));
+        assert_eq!(config.decode(pulse(950, 95)), Ok(1000));
+
+        let config = PwmConfig {
+            period_us: 10_000,
+            period_tolerance_us: 0,
+            full_scale_ppm: 5000,
+        };
+        assert_eq!(config.decode(pulse(10_000, 1_234)), Ok(617));
+    }
+
+    #[test]
+    fn test_invalid_pulse() {
+        let config = PwmConfig::default();
+        assert_eq!(config.decode(pulse(1101, 50)), Err(InvalidPulse::Period));
+        assert_eq!(config.decode(pulse(899, 50)), Err(InvalidPulse::Period));
+        assert_eq!(config.decode(pulse(1000, 0)), Err(InvalidPulse::HighTime));
+        assert_eq!(
+            config.decode(pulse(1000, 1000)),
+            Err(InvalidPulse::HighTime)
+        );
+        assert_eq!(
+            config.decode(pulse(1000, 1200)),
+            Err(InvalidPulse::HighTime)
+        );
+
+        let config = PwmConfig {
+            period_us: 50,
+            period_tolerance_us: 100,
+            ..Default::default()
+        };
+        assert_eq!(config.decode(pulse(0, 0)), Err(InvalidPulse::Period));
+    }
+
+    #[test]
+    fn test_large_full_scale() {
+        let config = PwmConfig {
+            full_scale_ppm: 40_000,
+            ..Default::default()
+        };
+        assert_eq!(config.decode(pulse(1000, 819)), Ok(32_760));
+        assert_eq!(
+            config.decode(pulse(1000, 820)),
+            Err(InvalidPulse::Concentration)
+        );
+        assert_eq!(
+            config.decode(pulse(1000, 900)),
+            Err(InvalidPulse::Concentration)
+        );
+    }
+}
@@ -1,6 +1,8 @@
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 
 #[derive(IntoPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 #[repr(u8)]
 pub enum Register {
     ProdId = 0x0,
@@ -18,6 +20,36 @@ pub enum Register {
 }
 
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ProductId {
+    /// Product type
+    pub product: u8,
+    /// Firmware revision
+    pub revision: u8,
+}
+
+impl ProductId {
+    /// Product type of the XENSIV PAS CO2 sensor
+    pub const PAS_CO2: u8 = 0b010;
+
+    /// Whether the product type is a XENSIV PAS CO2 sensor
+    pub fn is_pas_co2(&self) -> bool {
+        self.product == Self::PAS_CO2
+    }
+}
+
+impl From<u8> for ProductId {
+    fn from(value: u8) -> Self {
+        Self {
+            product: value >> 5,
+            revision: value & 0b0001_1111,
+        }
+    }
+}
+
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub struct Status {
     /// Sensor ready bit
@@ -32,6 +64,21 @@ pub struct Status {
     pub communication_error: bool,
 }
 
+impl Status {
+    /// The first fault reported, if any
+    pub fn fault(&self) -> Option<crate::SensorFault> {
+        if self.temperature_error {
+            Some(crate::SensorFault::Temperature)
+        } else if self.voltage_error {
+            Some(crate::SensorFault::Voltage)
+        } else if self.communication_error {
+            Some(crate::SensorFault::Communication)
+        } else {
+            None
+        }
+    }
+}
+
 impl From<u8> for Status {
     fn from(value: u8) -> Self {
         Self {
@@ -45,6 +92,7 @@ impl From<u8> for Status {
 }
 
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub struct MeasurementStatus {
     /// New data available in CO2PPM Register
@@ -66,7 +114,8 @@ impl From<u8> for MeasurementStatus {
 }
 
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-#[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub struct MeasurementMode {
     /// PWM output software enable bit
     pub pwm_out_enable: bool,
@@ -78,27 +127,35 @@ pub struct MeasurementMode {
     pub operating_mode: OperatingMode,
 }
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-#[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum PwmMode {
     SinglePulse = 0,
     PulseTrain = 1,
 }
 
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-#[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum BaselineOffsetCompensation {
     Disabled = 0b00,
     Enabled = 0b01,
     Forced = 0b10,
+    #[cfg_attr(feature = "serde", serde(rename = "reserved"))]
     _Reserved = 0b11,
 }
 
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-#[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum OperatingMode {
     Idle = 0b00,
     SingleShot = 0b01,
     Continuous = 0b10,
+    #[cfg_attr(feature = "serde", serde(rename = "reserved"))]
     _Reserved = 0b11,
 }
 
@@ -142,6 +199,7 @@ impl From<u8> for MeasurementMode {
 }
 
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub struct InterruptConfig {
     /// Pin INT electrical config: false = active low, true = active high
@@ -153,6 +211,8 @@ pub struct InterruptConfig {
 }
 
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 #[derive(Clone, Copy, Debug, PartialEq, IntoPrimitive, TryFromPrimitive)]
 #[repr(u8)]
 pub enum IntFunctionConfig {
@@ -199,9 +259,11 @@ impl TryFrom<u8> for InterruptConfig {
     }
 }
 
+/// Soft reset register
 #[derive(IntoPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 #[repr(u8)]
-/// Soft reset register
 pub enum SoftReset {
     /// Trigger a soft reset event
     SoftReset = 0xA3,
@@ -210,17 +272,169 @@ pub enum SoftReset {
     /// Save the force calibration offset to internal NVM immediately
     SaveForceCalibNvm = 0xCF,
     /// Disable the stepwise reactive IIR filter
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "disable_stepwise_reactive_iir_filter")
+    )]
     DisableStepwiseReractiveIirFilter = 0xDF,
     /// Reset the forced calibration correction factor
     ResetForcedCalibCorrectionFactor = 0xFC,
     /// Enable the stepwise reactive IIR filter (default enabled)
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "enable_stepwise_reactive_iir_filter")
+    )]
     EnableStepwiseReaciveIirFilter = 0xFE,
 }
 
+/// Error when creating a register value outside of the range accepted by the sensor
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct OutOfRangeError;
+
+/// Defines a newtype for a register value that is valid within `MIN..=MAX`
+macro_rules! ranged_value {
+    ($(#[$meta:meta])* $name:ident($inner:ty), $min:expr, $max:expr) => {
+        $(#[$meta])*
+        #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+        pub struct $name($inner);
+
+        impl $name {
+            /// Smallest valid value
+            pub const MIN: Self = Self($min);
+            /// Largest valid value
+            pub const MAX: Self = Self($max);
+
+            /// Create a new value, fails if `value` is outside of `MIN..=MAX`
+            pub const fn new(value: $inner) -> Result<Self, OutOfRangeError> {
+                if value >= $min && value <= $max {
+                    Ok(Self(value))
+                } else {
+                    Err(OutOfRangeError)
+                }
+            }
+
+            /// Create a new value, clamping `value` to `MIN..=MAX`
+            pub const fn new_saturating(value: $inner) -> Self {
+                if value < $min {
+                    Self::MIN
+                } else if value > $max {
+                    Self::MAX
+                } else {
+                    Self(value)
+                }
+            }
+
+            /// Get the raw value
+            pub const fn get(self) -> $inner {
+                self.0
+            }
+        }
+
+        impl TryFrom<$inner> for $name {
+            type Error = OutOfRangeError;
+
+            fn try_from(value: $inner) -> Result<Self, Self::Error> {
+                Self::new(value)
+            }
+        }
+
+        impl From<$name> for $inner {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                self.0.serialize(serializer)
+            }
+        }
+
+        /// Fails for values outside of `MIN..=MAX`
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = <$inner>::deserialize(deserializer)?;
+                Self::new(value).map_err(|_| serde::de::Error::custom("value out of range"))
+            }
+        }
+    };
+}
+
+ranged_value!(
+    /// Time between two measurements in continuous mode in seconds, 5 s to 4095 s
+    MeasurementPeriod(u16),
+    5,
+    4095
+);
+
+ranged_value!(
+    /// Ambient pressure used for pressure compensation in hPa, 750 hPa to 1150 hPa
+    PressureHpa(u16),
+    750,
+    1150
+);
+
+/// Mean sea level pressure of the ICAO standard atmosphere in hPa
+pub const STANDARD_SEA_LEVEL_HPA: f32 = 1013.25;
+
+impl PressureHpa {
+    /// Pressure at `altitude_m` meters above sea level in the standard atmosphere, given the
+    /// pressure `sea_level_hpa` at sea level (usually [STANDARD_SEA_LEVEL_HPA])
+    ///
+    /// Uses the barometric formula of the ICAO standard atmosphere, rounded to whole hPa.
+    /// At standard sea level pressure, results are within `MIN..=MAX` from about -1080 m to
+    /// 2460 m, fails outside.
+    pub fn from_altitude(altitude_m: f32, sea_level_hpa: f32) -> Result<Self, OutOfRangeError> {
+        let hpa = sea_level_hpa * libm::powf(1.0 - 2.25577e-5 * altitude_m, 5.25588);
+        let hpa = libm::roundf(hpa);
+        // Also rejects NaN, e.g. for altitudes above the standard atmosphere
+        if (f32::from(Self::MIN.0)..=f32::from(Self::MAX.0)).contains(&hpa) {
+            Ok(Self(hpa as u16))
+        } else {
+            Err(OutOfRangeError)
+        }
+    }
+}
+
+ranged_value!(
+    /// Reference CO2 concentration for baseline offset compensation in PPM, 350 ppm to 900 ppm
+    CalibrationPpm(i16),
+    350,
+    900
+);
+
+ranged_value!(
+    /// Threshold for the alarm in PPM
+    ///
+    /// The register is signed, but negative thresholds make no sense. 0 is the reset value.
+    AlarmThresholdPpm(i16),
+    0,
+    i16::MAX
+);
+
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[test]
+    fn test_product_id_bitmask() {
+        let id = ProductId::from(0b0100_0011);
+
+        assert_eq!(
+            id,
+            ProductId {
+                product: ProductId::PAS_CO2,
+                revision: 3
+            }
+        );
+        assert!(id.is_pas_co2());
+        assert!(!ProductId::from(0b1100_0011).is_pas_co2());
+    }
+
     #[test]
     fn test_status_bitmask() {
         let status = Status {
@@ -236,6 +450,23 @@ mod test {
         assert_eq!(status, Status::from(bitmask))
     }
 
+    #[test]
+    fn test_status_fault() {
+        assert_eq!(Status::from(0b1000_0000).fault(), None);
+        assert_eq!(
+            Status::from(0b1011_1000).fault(),
+            Some(crate::SensorFault::Temperature)
+        );
+        assert_eq!(
+            Status::from(0b1001_1000).fault(),
+            Some(crate::SensorFault::Voltage)
+        );
+        assert_eq!(
+            Status::from(0b1000_1000).fault(),
+            Some(crate::SensorFault::Communication)
+        );
+    }
+
     #[test]
     fn test_measurement_status_bitmask() {
         let status = MeasurementStatus {
@@ -281,4 +512,42 @@ mod test {
         let config_from = InterruptConfig::try_from(bitmask).unwrap();
         assert_eq!(config, config_from);
     }
+
+    #[test]
+    fn test_ranged_values() {
+        assert_eq!(MeasurementPeriod::new(4), Err(OutOfRangeError));
+        assert_eq!(MeasurementPeriod::new(5), Ok(MeasurementPeriod::MIN));
+        assert_eq!(MeasurementPeriod::new(4095), Ok(MeasurementPeriod::MAX));
+        assert_eq!(MeasurementPeriod::new(4096), Err(OutOfRangeError));
+
+        assert!(PressureHpa::try_from(749).is_err());
+        assert_eq!(u16::from(PressureHpa::try_from(1013).unwrap()), 1013);
+        assert_eq!(PressureHpa::new_saturating(0), PressureHpa::MIN);
+        assert_eq!(PressureHpa::new_saturating(1013).get(), 1013);
+        assert_eq!(PressureHpa::new_saturating(u16::MAX), PressureHpa::MAX);
+
+        assert!(CalibrationPpm::new(901).is_err());
+        assert_eq!(CalibrationPpm::new(400).unwrap().get(), 400);
+
+        assert!(AlarmThresholdPpm::new(-1).is_err());
+        assert_eq!(AlarmThresholdPpm::new(0), Ok(AlarmThresholdPpm::MIN));
+    }
+
+    #[test]
+    fn test_pressure_from_altitude() {
+        let at = |altitude_m, sea_level_hpa| {
+            PressureHpa::from_altitude(altitude_m, sea_level_hpa).map(PressureHpa::get)
+        };
+        assert_eq!(at(0.0, STANDARD_SEA_LEVEL_HPA), Ok(1013));
+        assert_eq!(at(500.0, STANDARD_SEA_LEVEL_HPA), Ok(955));
+        assert_eq!(at(1000.0, STANDARD_SEA_LEVEL_HPA), Ok(899));
+        assert_eq!(at(2000.0, STANDARD_SEA_LEVEL_HPA), Ok(795));
+        assert_eq!(at(500.0, 1030.0), Ok(970));
+        assert_eq!(at(-400.0, STANDARD_SEA_LEVEL_HPA), Ok(1062));
+
+        assert_eq!(at(2500.0, STANDARD_SEA_LEVEL_HPA), Err(OutOfRangeError));
+        assert_eq!(at(-1200.0, STANDARD_SEA_LEVEL_HPA), Err(OutOfRangeError));
+        assert_eq!(at(50_000.0, STANDARD_SEA_LEVEL_HPA), Err(OutOfRangeError));
+        assert_eq!(at(f32::NAN, STANDARD_SEA_LEVEL_HPA), Err(OutOfRangeError));
+    }
 }
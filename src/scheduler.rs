@@ -0,0 +1,242 @@
+use core::convert::Infallible;
+
+use embedded_hal::digital::{ErrorType, OutputPin};
+
+/// Default of [DutyCycleConfig::warm_up_ms]
+///
+/// Time for the 12 V emitter supply to settle after switching it on. Depends on the supply,
+/// check it for your board.
+pub const DEFAULT_WARM_UP_MS: u32 = 1000;
+
+/// Configuration of [PasCo2::start_duty_cycled()](crate::PasCo2::start_duty_cycled())
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DutyCycleConfig {
+    /// Time from the start of one measurement cycle to the start of the next
+    ///
+    /// If a cycle takes longer, the next one starts right away.
+    pub interval_ms: u32,
+    /// Time the emitter supply is switched on before each measurement
+    ///
+    /// Only applies with a supply pin, see
+    /// [DutyCycled::with_emitter_supply()](crate::DutyCycled::with_emitter_supply()).
+    pub warm_up_ms: u32,
+    /// Timeout of each measurement, see [PasCo2::measure_single_shot()](crate::PasCo2::measure_single_shot())
+    pub timeout_ms: u32,
+}
+
+impl DutyCycleConfig {
+    /// Measure every `interval_ms` with [DEFAULT_WARM_UP_MS] and a timeout of 2 s
+    pub fn new(interval_ms: u32) -> Self {
+        Self {
+            interval_ms,
+            warm_up_ms: DEFAULT_WARM_UP_MS,
+            timeout_ms: 2000,
+        }
+    }
+}
+
+/// Statistics of a [DutyCycled](crate::DutyCycled) scheduler
+///
+/// Times are accounted from the delays the scheduler performs, so time spent between calls to
+/// [DutyCycled::next_reading()](crate::DutyCycled::next_reading()) is not included.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct DutyCycleReport {
+    /// Number of successful measurements
+    pub measurements: u32,
+    /// Time the sensor was warming up or measuring
+    pub active_ms: u64,
+    /// Total time of all measurement cycles so far
+    pub elapsed_ms: u64,
+}
+
+impl DutyCycleReport {
+    /// Share of the active time in the elapsed time in permille
+    pub fn duty_cycle_permille(&self) -> u32 {
+        if self.elapsed_ms == 0 {
+            return 0;
+        }
+        (self.active_ms * 1000 / self.elapsed_ms) as u32
+    }
+}
+
+/// Placeholder for a [DutyCycled](crate::DutyCycled) scheduler without emitter supply pin
+pub struct NoSupply;
+
+impl ErrorType for NoSupply {
+    type Error = Infallible;
+}
+
+impl OutputPin for NoSupply {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+// See driver.rs on how the async and blocking variants are generated.
+#[maybe_async_cfg::maybe(sync(self = "blocking"), async(self = "asynchronous"))]
+pub mod scheduler {
+    #[maybe_async_cfg::only_if(async)]
+    use crate::driver::asynchronous::PasCo2;
+    #[maybe_async_cfg::only_if(sync)]
+    use crate::driver::blocking::PasCo2;
+    #[maybe_async_cfg::only_if(async)]
+    use crate::interface::asynchronous::Interface;
+    #[maybe_async_cfg::only_if(sync)]
+    use crate::interface::blocking::Interface;
+    #[maybe_async_cfg::only_if(sync)]
+    use embedded_hal::delay::DelayNs;
+    #[maybe_async_cfg::only_if(async)]
+    use embedded_hal_async::delay::DelayNs;
+
+    use embedded_hal::digital::{Error as _, OutputPin};
+
+    use super::{DutyCycleConfig, DutyCycleReport, NoSupply};
+    use crate::Error;
+
+    /// Single measurements at a fixed interval, see [PasCo2::start_duty_cycled()]
+    ///
+    /// The sensor stays in idle mode between measurements. Optionally, the 12 V emitter supply
+    /// is switched off in between, see [Self::with_emitter_supply()].
+    pub struct DutyCycled<'a, T: Interface, D: DelayNs, P: OutputPin> {
+        driver: &'a mut PasCo2<T>,
+        delay: D,
+        config: DutyCycleConfig,
+        /// Switches the emitter supply, high is on
+        supply: Option<P>,
+        /// Time to wait before the next cycle starts
+        idle_due_ms: u32,
+        report: DutyCycleReport,
+    }
+
+    impl<T> PasCo2<T>
+    where
+        T: Interface,
+    {
+        /// Measure in single shot mode at the interval given by `config`
+        ///
+        /// Puts the sensor into idle mode. The returned [DutyCycled] measures once per call of
+        /// [DutyCycled::next_reading()], waiting for the interval to pass first.
+        pub async fn start_duty_cycled<D: DelayNs>(
+            &mut self,
+            config: DutyCycleConfig,
+            delay: D,
+        ) -> Result<DutyCycled<'_, T, D, NoSupply>, Error<T::Error>> {
+            self.set_idle().await?;
+
+            Ok(DutyCycled {
+                driver: self,
+                delay,
+                config,
+                supply: None,
+                idle_due_ms: 0,
+                report: DutyCycleReport::default(),
+            })
+        }
+    }
+
+    impl<'a, T, D, P> DutyCycled<'a, T, D, P>
+    where
+        T: Interface,
+        D: DelayNs,
+        P: OutputPin,
+    {
+        /// Switch the 12 V emitter supply with `pin` (high is on)
+        ///
+        /// The supply is switched off right away and between measurements. Before each
+        /// measurement, it is switched on for [DutyCycleConfig::warm_up_ms]. The out-of-range
+        /// supply voltage error this raises is cleared after the warm-up, so it neither fails
+        /// the measurement with fault checking enabled (see [PasCo2::set_fault_checking()]) nor
+        /// lingers in the sensor's status.
+        pub fn with_emitter_supply<S: OutputPin>(
+            self,
+            mut pin: S,
+        ) -> Result<DutyCycled<'a, T, D, S>, Error<T::Error>> {
+            pin.set_low().map_err(|e| Error::Pin(e.kind()))?;
+
+            Ok(DutyCycled {
+                driver: self.driver,
+                delay: self.delay,
+                config: self.config,
+                supply: Some(pin),
+                idle_due_ms: self.idle_due_ms,
+                report: self.report,
+            })
+        }
+
+        /// Wait for the next cycle, measure and return the reading in PPM
+        ///
+        /// The first call measures right away. If the measurement fails, the sensor is put back
+        /// into idle mode. The emitter supply is switched off afterwards in either case.
+        pub async fn next_reading(&mut self) -> Result<i16, Error<T::Error>> {
+            self.delay.delay_ms(self.idle_due_ms).await;
+            self.report.elapsed_ms += u64::from(self.idle_due_ms);
+
+            let mut active_ms = 0;
+            let mut warmed_up = Ok(());
+            if let Some(pin) = &mut self.supply {
+                pin.set_high().map_err(|e| Error::Pin(e.kind()))?;
+                self.delay.delay_ms(self.config.warm_up_ms).await;
+                active_ms += self.config.warm_up_ms;
+                // Switching the supply off latched the out-of-range supply voltage error
+                warmed_up = self.driver.clear_voltage_error().await;
+            }
+
+            let result = match warmed_up {
+                Ok(()) => {
+                    self.driver
+                        .measure_single_shot_timed(&mut self.delay, self.config.timeout_ms)
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+            // Abort a failed measurement, retrying before the next transfer if that fails too
+            if result.is_err() && self.driver.set_idle().await.is_err() {
+                self.driver.idle_pending = true;
+            }
+            let supply_off = match &mut self.supply {
+                Some(pin) => pin.set_low().map_err(|e| Error::Pin(e.kind())),
+                None => Ok(()),
+            };
+
+            active_ms += match result {
+                Ok((_, waited_ms)) => waited_ms,
+                Err(Error::Timeout) => self.config.timeout_ms,
+                Err(_) => 0,
+            };
+            self.report.active_ms += u64::from(active_ms);
+            self.report.elapsed_ms += u64::from(active_ms);
+            self.idle_due_ms = self.config.interval_ms.saturating_sub(active_ms);
+
+            let (co2_ppm, _) = result?;
+            supply_off?;
+            self.report.measurements += 1;
+            Ok(co2_ppm)
+        }
+
+        /// Statistics of the measurements so far, e.g. the achieved duty cycle
+        pub fn report(&self) -> DutyCycleReport {
+            self.report
+        }
+    }
+
+    #[maybe_async_cfg::only_if(sync)]
+    impl<T, D, P> Iterator for DutyCycled<'_, T, D, P>
+    where
+        T: Interface,
+        D: DelayNs,
+        P: OutputPin,
+    {
+        type Item = Result<i16, Error<T::Error>>;
+
+        /// Wait for the next reading, see [Self::next_reading()]. Never returns `None`.
+        fn next(&mut self) -> Option<Self::Item> {
+            Some(self.next_reading())
+        }
+    }
+}
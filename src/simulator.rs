@@ -0,0 +1,591 @@
+//! Host-side simulation of the sensor's I2C register map
+//!
+//! [Simulator] implements the async and blocking `I2c` traits and can therefore be passed to
+//! [PasCo2::new](crate::PasCo2::new) and [blocking::PasCo2::new](crate::blocking::PasCo2::new).
+//! Time only advances through [SimDelay] (or [Simulator::advance_ms]), so tests that wait for
+//! measurements complete instantly and deterministically.
+//!
+//! ```
+//! use pas_co2_rs::{regs::*, simulator::Simulator, PasCo2};
+//!
+//! let sim = Simulator::new();
+//! sim.set_co2_ppm(850);
+//!
+//! let mut pas_co2 = PasCo2::new(sim.clone());
+//! embassy_futures::block_on(async {
+//!     pas_co2.start_measurement().await.unwrap();
+//!     sim.advance_ms(1000);
+//!     assert!(pas_co2.get_measurement_status().await.unwrap().data_ready);
+//!     assert_eq!(pas_co2.get_co2_ppm().await.unwrap(), 850);
+//! });
+//! ```
+
+use core::cell::RefCell;
+use std::rc::Rc;
+
+use embedded_hal::digital;
+use embedded_hal::i2c::{ErrorKind, ErrorType, NoAcknowledgeSource, Operation, SevenBitAddress};
+
+use crate::regs::{Register, SoftReset};
+use crate::ADDRESS;
+
+/// Time from the start of a measurement until its result is available
+pub const MEASUREMENT_DURATION_MS: u64 = 1000;
+
+/// Time from power-on until the sensor is ready, see [Simulator::power_on]
+pub const STARTUP_DURATION_MS: u64 = 500;
+
+const PROD_ID: u8 = Register::ProdId as u8;
+const SENS_STS: u8 = Register::SensorStatus as u8;
+const MEAS_RATE_H: u8 = Register::MeasurementRate as u8;
+const MEAS_RATE_L: u8 = MEAS_RATE_H + 1;
+const MEAS_CFG: u8 = Register::MeasurementMode as u8;
+const CO2PPM_H: u8 = Register::Co2Ppm as u8;
+const CO2PPM_L: u8 = CO2PPM_H + 1;
+const MEAS_STS: u8 = Register::MeasurementStatus as u8;
+const INT_CFG: u8 = Register::InterruptConfig as u8;
+const ALARM_TH_H: u8 = Register::AlarmThreshold as u8;
+const PRESS_REF_H: u8 = Register::PressureReference as u8;
+const CALIB_REF_H: u8 = Register::CalibrationReference as u8;
+const SENS_RST: u8 = Register::SensorReset as u8;
+const REGISTER_COUNT: usize = SENS_RST as usize + 1;
+
+// SENS_STS bits
+const SEN_RDY: u8 = 1 << 7;
+const ORTMP: u8 = 1 << 5;
+const ORVS: u8 = 1 << 4;
+const ICCERR: u8 = 1 << 3;
+
+// MEAS_STS bits
+const DRDY: u8 = 1 << 4;
+const INT_STS: u8 = 1 << 3;
+const ALARM: u8 = 1 << 2;
+
+// OP_MODE values of MEAS_CFG
+const OP_MODE_MASK: u8 = 0b11;
+const OP_MODE_IDLE: u8 = 0b00;
+const OP_MODE_SINGLE_SHOT: u8 = 0b01;
+const OP_MODE_CONTINUOUS: u8 = 0b10;
+
+// INT_FUNC values of INT_CFG
+const INT_FUNC_ALARM: u8 = 0x1;
+const INT_FUNC_DATA_READY: u8 = 0x2;
+const INT_FUNC_BUSY: u8 = 0x3;
+const INT_FUNC_MAX: u8 = 0x4;
+
+/// Register values after power-up or a soft reset
+#[rustfmt::skip]
+const RESET_VALUES: [u8; REGISTER_COUNT] = [
+    0x42,       // PROD_ID: PAS CO2, revision 2
+    SEN_RDY,    // SENS_STS
+    0x00, 0x3C, // MEAS_RATE: 60 s
+    0x24,       // MEAS_CFG: PWM output enabled, ABOC enabled, idle
+    0x00, 0x00, // CO2PPM
+    0x00,       // MEAS_STS
+    0x11,       // INT_CFG: active high, inactive, alarm on crossing up
+    0x00, 0x00, // ALARM_TH
+    0x03, 0xF7, // PRESS_REF: 1015 hPa
+    0x01, 0x90, // CALIB_REF: 400 ppm
+    0x00,       // SCRATCH_PAD
+    0x00,       // SENS_RST
+];
+
+/// Simulated PAS CO2 sensor on an I2C bus
+///
+/// Clones share the same sensor, so a clone can be handed to the driver while the test keeps
+/// another one to control the environment and inspect the registers.
+#[derive(Clone, Default)]
+pub struct Simulator {
+    state: Rc<RefCell<State>>,
+}
+
+/// [DelayNs](embedded_hal::delay::DelayNs) that advances the time of a [Simulator]
+#[derive(Clone)]
+pub struct SimDelay {
+    state: Rc<RefCell<State>>,
+}
+
+/// INT pin of a [Simulator]
+///
+/// Awaiting a level or edge lets the simulated time pass until the sensor drives the pin
+/// accordingly, or fails with [digital::ErrorKind::Other] if it never will.
+/// Every poll of the pin's level lets [PIN_POLL_DURATION_US] pass.
+#[derive(Clone)]
+pub struct SimIntPin {
+    state: Rc<RefCell<State>>,
+}
+
+/// Time that passes on the simulator with every poll of [SimIntPin]'s level
+pub const PIN_POLL_DURATION_US: u64 = 10;
+
+struct State {
+    regs: [u8; REGISTER_COUNT],
+    now_ns: u64,
+    co2_ppm: i16,
+    last_result: Option<i16>,
+    /// Time at which the running measurement completes
+    measurement_done_ns: Option<u64>,
+    iir_filter_enabled: bool,
+    forced_calibration_saved: bool,
+    nacks_to_inject: usize,
+    /// Time at which the sensor becomes ready after power-on
+    ready_ns: u64,
+    /// Whether the 12 V emitter supply is on
+    emitter_supply: bool,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            regs: RESET_VALUES,
+            now_ns: 0,
+            co2_ppm: 400,
+            last_result: None,
+            measurement_done_ns: None,
+            iir_filter_enabled: true,
+            forced_calibration_saved: false,
+            nacks_to_inject: 0,
+            ready_ns: 0,
+            emitter_supply: true,
+        }
+    }
+}
+
+impl Simulator {
+    /// Create a new, powered up sensor with all registers at their reset values
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new sensor that has just been powered on
+    ///
+    /// The sensor ready bit is set after [STARTUP_DURATION_MS]. Until then, writes are lost.
+    pub fn power_on() -> Self {
+        let sim = Self::new();
+        {
+            let mut state = sim.state.borrow_mut();
+            state.regs[SENS_STS as usize] &= !SEN_RDY;
+            state.ready_ns = STARTUP_DURATION_MS * 1_000_000;
+        }
+        sim
+    }
+
+    /// Obtain a delay that advances this sensor's time
+    pub fn delay(&self) -> SimDelay {
+        SimDelay {
+            state: self.state.clone(),
+        }
+    }
+
+    /// Obtain the sensor's INT pin
+    pub fn int_pin(&self) -> SimIntPin {
+        SimIntPin {
+            state: self.state.clone(),
+        }
+    }
+
+    /// Set the CO2 concentration the sensor measures from now on
+    pub fn set_co2_ppm(&self, ppm: i16) {
+        self.state.borrow_mut().co2_ppm = ppm;
+    }
+
+    /// Advance the sensor's time by `ms` milliseconds
+    pub fn advance_ms(&self, ms: u64) {
+        self.state.borrow_mut().advance_ns(ms * 1_000_000);
+    }
+
+    /// Time in milliseconds since the sensor was created
+    pub fn now_ms(&self) -> u64 {
+        self.state.borrow().now_ns / 1_000_000
+    }
+
+    /// Raw value of the register at `address`, without read side effects
+    pub fn peek(&self, address: u8) -> u8 {
+        self.state.borrow().regs[address as usize]
+    }
+
+    /// Overwrite the raw value of the register at `address`, without write side effects
+    pub fn poke(&self, address: u8, value: u8) {
+        self.state.borrow_mut().regs[address as usize] = value;
+    }
+
+    /// Raise the out-of-range temperature error
+    pub fn raise_temperature_error(&self) {
+        self.state.borrow_mut().regs[SENS_STS as usize] |= ORTMP;
+    }
+
+    /// Raise the out-of-range supply voltage error
+    pub fn raise_voltage_error(&self) {
+        self.state.borrow_mut().regs[SENS_STS as usize] |= ORVS;
+    }
+
+    /// Switch the 12 V emitter supply
+    ///
+    /// While it is off, the out-of-range supply voltage error is raised and cannot be cleared.
+    pub fn set_emitter_supply(&self, on: bool) {
+        let mut state = self.state.borrow_mut();
+        state.emitter_supply = on;
+        if !on {
+            state.regs[SENS_STS as usize] |= ORVS;
+        }
+    }
+
+    /// Let the next `count` transfers fail with a NACK, as the sensor does while busy
+    pub fn inject_nacks(&self, count: usize) {
+        self.state.borrow_mut().nacks_to_inject = count;
+    }
+
+    /// Whether the stepwise reactive IIR filter is enabled
+    pub fn iir_filter_enabled(&self) -> bool {
+        self.state.borrow().iir_filter_enabled
+    }
+
+    /// Whether the forced calibration offset has been saved to NVM
+    pub fn forced_calibration_saved(&self) -> bool {
+        self.state.borrow().forced_calibration_saved
+    }
+
+    /// Whether the INT pin is active, independent of its configured electrical polarity
+    pub fn int_pin_active(&self) -> bool {
+        self.state.borrow().int_pin_active()
+    }
+}
+
+impl State {
+    fn advance_ns(&mut self, ns: u64) {
+        let until = self.now_ns + ns;
+        while let Some(done) = self.measurement_done_ns.filter(|done| *done <= until) {
+            self.now_ns = done;
+            self.complete_measurement();
+        }
+        self.now_ns = until;
+
+        if self.now_ns >= self.ready_ns {
+            self.regs[SENS_STS as usize] |= SEN_RDY;
+        }
+    }
+
+    fn ready(&self) -> bool {
+        self.regs[SENS_STS as usize] & SEN_RDY != 0
+    }
+
+    fn op_mode(&self) -> u8 {
+        self.regs[MEAS_CFG as usize] & OP_MODE_MASK
+    }
+
+    fn int_function(&self) -> u8 {
+        (self.regs[INT_CFG as usize] >> 1) & 0b111
+    }
+
+    fn int_pin_active(&self) -> bool {
+        match self.int_function() {
+            INT_FUNC_BUSY => self.measurement_done_ns.is_some(),
+            INT_FUNC_ALARM | INT_FUNC_DATA_READY => self.regs[MEAS_STS as usize] & INT_STS != 0,
+            _ => false,
+        }
+    }
+
+    /// Electrical level of the INT pin
+    fn int_pin_high(&self) -> bool {
+        let active_high = self.regs[INT_CFG as usize] & (1 << 4) != 0;
+        self.int_pin_active() == active_high
+    }
+
+    /// Advance to the next point in time at which the INT pin may change.
+    ///
+    /// Returns false if there is none, i.e. the pin keeps its level forever.
+    fn advance_to_next_event(&mut self) -> bool {
+        match self.measurement_done_ns {
+            Some(done) => {
+                self.advance_ns(done - self.now_ns);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn measurement_period_ns(&self) -> u64 {
+        let rate = u16::from_be_bytes([
+            self.regs[MEAS_RATE_H as usize],
+            self.regs[MEAS_RATE_L as usize],
+        ]);
+        u64::from(rate) * 1_000_000_000
+    }
+
+    fn complete_measurement(&mut self) {
+        let ppm = self.co2_ppm;
+        let [high, low] = ppm.to_be_bytes();
+        self.regs[CO2PPM_H as usize] = high;
+        self.regs[CO2PPM_L as usize] = low;
+        self.regs[MEAS_STS as usize] |= DRDY;
+
+        let threshold = i16::from_be_bytes([
+            self.regs[ALARM_TH_H as usize],
+            self.regs[ALARM_TH_H as usize + 1],
+        ]);
+        let crossing_up = self.regs[INT_CFG as usize] & 0b1 != 0;
+        let alarm = match self.last_result {
+            Some(last) if crossing_up => last < threshold && ppm >= threshold,
+            Some(last) => last > threshold && ppm <= threshold,
+            None => false,
+        };
+        if alarm {
+            self.regs[MEAS_STS as usize] |= ALARM;
+        }
+        if (alarm && self.int_function() == INT_FUNC_ALARM)
+            || self.int_function() == INT_FUNC_DATA_READY
+        {
+            self.regs[MEAS_STS as usize] |= INT_STS;
+        }
+        self.last_result = Some(ppm);
+
+        if self.op_mode() == OP_MODE_CONTINUOUS {
+            self.measurement_done_ns = Some(self.now_ns + self.measurement_period_ns());
+        } else {
+            self.regs[MEAS_CFG as usize] &= !OP_MODE_MASK;
+            self.measurement_done_ns = None;
+        }
+    }
+
+    fn read(&mut self, address: u8) -> Result<u8, ErrorKind> {
+        let value = *self
+            .regs
+            .get(address as usize)
+            .ok_or(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data))?;
+        if address == MEAS_STS {
+            // DRDY is cleared by reading the measurement status
+            self.regs[MEAS_STS as usize] &= !DRDY;
+        }
+        Ok(value)
+    }
+
+    fn write(&mut self, address: u8, value: u8) -> Result<(), ErrorKind> {
+        let ready = self.ready();
+        let reg = self
+            .regs
+            .get_mut(address as usize)
+            .ok_or(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data))?;
+        if !ready {
+            // Writes are lost until the sensor is ready after power-on
+            return Ok(());
+        }
+        match address {
+            PROD_ID | CO2PPM_H | CO2PPM_L => (),
+            // Bits 2:0 clear the error flags in bits 5:3
+            SENS_STS => {
+                *reg &= !((value & 0b111) << 3);
+                if !self.emitter_supply {
+                    *reg |= ORVS;
+                }
+            }
+            // Bits 1:0 clear the INT_STS and ALARM flags in bits 3:2
+            MEAS_STS => *reg &= !((value & 0b11) << 2),
+            MEAS_CFG => {
+                let previous = *reg & OP_MODE_MASK;
+                *reg = value & 0b0011_1111;
+                self.start_operating_mode(previous);
+            }
+            INT_CFG => {
+                *reg = value & 0b0001_1111;
+                if self.int_function() > INT_FUNC_MAX {
+                    self.regs[SENS_STS as usize] |= ICCERR;
+                }
+            }
+            SENS_RST => self.soft_reset(value),
+            _ => *reg = value,
+        }
+        Ok(())
+    }
+
+    fn start_operating_mode(&mut self, previous: u8) {
+        match self.op_mode() {
+            OP_MODE_IDLE => self.measurement_done_ns = None,
+            OP_MODE_SINGLE_SHOT if previous != OP_MODE_SINGLE_SHOT => {
+                self.measurement_done_ns = Some(self.now_ns + MEASUREMENT_DURATION_MS * 1_000_000)
+            }
+            OP_MODE_CONTINUOUS if previous != OP_MODE_CONTINUOUS => {
+                self.measurement_done_ns = Some(self.now_ns + MEASUREMENT_DURATION_MS * 1_000_000)
+            }
+            OP_MODE_SINGLE_SHOT | OP_MODE_CONTINUOUS => (),
+            _ => self.regs[SENS_STS as usize] |= ICCERR,
+        }
+    }
+
+    fn soft_reset(&mut self, code: u8) {
+        match code {
+            c if c == SoftReset::SoftReset as u8 => {
+                let co2_ppm = self.co2_ppm;
+                let now_ns = self.now_ns;
+                let iir_filter_enabled = self.iir_filter_enabled;
+                let forced_calibration_saved = self.forced_calibration_saved;
+                *self = Self {
+                    co2_ppm,
+                    now_ns,
+                    iir_filter_enabled,
+                    forced_calibration_saved,
+                    ..Default::default()
+                };
+            }
+            c if c == SoftReset::SaveForceCalibNvm as u8 => self.forced_calibration_saved = true,
+            c if c == SoftReset::DisableStepwiseReractiveIirFilter as u8 => {
+                self.iir_filter_enabled = false
+            }
+            c if c == SoftReset::EnableStepwiseReaciveIirFilter as u8 => {
+                self.iir_filter_enabled = true
+            }
+            c if c == SoftReset::AbocReset as u8
+                || c == SoftReset::ResetForcedCalibCorrectionFactor as u8 => {}
+            _ => self.regs[SENS_STS as usize] |= ICCERR,
+        }
+    }
+
+    /// Clip a two byte register to `min..=max`, raising a communication error if necessary
+    fn clip(&mut self, address: u8, min: i16, max: i16) {
+        let index = address as usize;
+        let value = i16::from_be_bytes([self.regs[index], self.regs[index + 1]]);
+        if !(min..=max).contains(&value) {
+            [self.regs[index], self.regs[index + 1]] = value.clamp(min, max).to_be_bytes();
+            self.regs[SENS_STS as usize] |= ICCERR;
+        }
+    }
+
+    fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), ErrorKind> {
+        if address != ADDRESS {
+            return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+        }
+        if self.nacks_to_inject > 0 {
+            self.nacks_to_inject -= 1;
+            return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+        }
+
+        let mut pointer = None;
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => {
+                    let (first, data) = match pointer {
+                        Some(address) => (address, &bytes[..]),
+                        None => match bytes.split_first() {
+                            Some((first, data)) => (*first, data),
+                            None => continue,
+                        },
+                    };
+                    for (address, byte) in (first..).zip(data) {
+                        self.write(address, *byte)?;
+                    }
+                    pointer = Some(first + data.len() as u8);
+                }
+                Operation::Read(buffer) => {
+                    let first = pointer.ok_or(ErrorKind::Other)?;
+                    for (address, byte) in (first..).zip(buffer.iter_mut()) {
+                        *byte = self.read(address)?;
+                    }
+                    pointer = Some(first + buffer.len() as u8);
+                }
+            }
+        }
+
+        // Multi byte registers are range checked once the transfer is complete
+        self.clip(MEAS_RATE_H, 5, 4095);
+        self.clip(PRESS_REF_H, 750, 1150);
+        self.clip(CALIB_REF_H, 350, 900);
+
+        Ok(())
+    }
+}
+
+impl ErrorType for Simulator {
+    type Error = ErrorKind;
+}
+
+impl embedded_hal::i2c::I2c<SevenBitAddress> for Simulator {
+    fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.state.borrow_mut().transaction(address, operations)
+    }
+}
+
+impl embedded_hal_async::i2c::I2c<SevenBitAddress> for Simulator {
+    async fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.state.borrow_mut().transaction(address, operations)
+    }
+}
+
+impl embedded_hal::delay::DelayNs for SimDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        self.state.borrow_mut().advance_ns(ns.into());
+    }
+}
+
+impl embedded_hal_async::delay::DelayNs for SimDelay {
+    async fn delay_ns(&mut self, ns: u32) {
+        self.state.borrow_mut().advance_ns(ns.into());
+    }
+}
+
+impl SimIntPin {
+    fn is_high(&mut self) -> bool {
+        let mut state = self.state.borrow_mut();
+        state.advance_ns(PIN_POLL_DURATION_US * 1000);
+        state.int_pin_high()
+    }
+
+    fn wait_for_level(&mut self, high: bool) -> Result<(), digital::ErrorKind> {
+        let mut state = self.state.borrow_mut();
+        while state.int_pin_high() != high {
+            if !state.advance_to_next_event() {
+                return Err(digital::ErrorKind::Other);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl digital::ErrorType for SimIntPin {
+    type Error = digital::ErrorKind;
+}
+
+impl digital::InputPin for SimIntPin {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(SimIntPin::is_high(self))
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(!SimIntPin::is_high(self))
+    }
+}
+
+impl embedded_hal_async::digital::Wait for SimIntPin {
+    async fn wait_for_high(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_level(true)
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_level(false)
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_level(false)?;
+        self.wait_for_level(true)
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_level(true)?;
+        self.wait_for_level(false)
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), Self::Error> {
+        let high = self.state.borrow().int_pin_high();
+        self.wait_for_level(!high)
+    }
+}
@@ -0,0 +1,123 @@
+use crate::regs::{
+    AlarmThresholdPpm, CalibrationPpm, InterruptConfig, MeasurementMode, MeasurementPeriod,
+    PressureHpa,
+};
+
+/// The sensor's configuration registers, see [PasCo2::read_snapshot()](crate::PasCo2::read_snapshot())
+///
+/// Read-only registers (status, CO2 value, product id) and write-to-clear bits are not part of
+/// the snapshot.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct RegisterSnapshot {
+    /// MEAS_RATE register
+    pub measurement_period: MeasurementPeriod,
+    /// MEAS_CFG register
+    pub measurement_mode: MeasurementMode,
+    /// INT_CFG register
+    pub interrupt_config: InterruptConfig,
+    /// ALARM_TH register
+    pub alarm_threshold: AlarmThresholdPpm,
+    /// PRESS_REF register
+    pub pressure: PressureHpa,
+    /// CALIB_REF register
+    pub calibration_reference: CalibrationPpm,
+}
+
+/// Registers that differ between two [RegisterSnapshot]s, see [RegisterSnapshot::diff()]
+///
+/// Each field is `true` if the respective register differs.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct SnapshotDiff {
+    pub measurement_period: bool,
+    pub measurement_mode: bool,
+    pub interrupt_config: bool,
+    pub alarm_threshold: bool,
+    pub pressure: bool,
+    pub calibration_reference: bool,
+}
+
+impl RegisterSnapshot {
+    /// Compare each register with the one in `other`
+    pub fn diff(&self, other: &Self) -> SnapshotDiff {
+        SnapshotDiff {
+            measurement_period: self.measurement_period != other.measurement_period,
+            measurement_mode: self.measurement_mode != other.measurement_mode,
+            interrupt_config: self.interrupt_config != other.interrupt_config,
+            alarm_threshold: self.alarm_threshold != other.alarm_threshold,
+            pressure: self.pressure != other.pressure,
+            calibration_reference: self.calibration_reference != other.calibration_reference,
+        }
+    }
+}
+
+impl SnapshotDiff {
+    /// Whether no register differs
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+// See driver.rs on how the async and blocking variants are generated.
+#[maybe_async_cfg::maybe(sync(self = "blocking"), async(self = "asynchronous"))]
+pub mod snapshot {
+    #[maybe_async_cfg::only_if(async)]
+    use crate::driver::asynchronous::PasCo2;
+    #[maybe_async_cfg::only_if(sync)]
+    use crate::driver::blocking::PasCo2;
+    #[maybe_async_cfg::only_if(async)]
+    use crate::interface::asynchronous::Interface;
+    #[maybe_async_cfg::only_if(sync)]
+    use crate::interface::blocking::Interface;
+
+    use super::RegisterSnapshot;
+    use crate::regs::OperatingMode;
+    use crate::Error;
+
+    impl<T> PasCo2<T>
+    where
+        T: Interface,
+    {
+        /// Read all configuration registers, see [RegisterSnapshot]
+        pub async fn read_snapshot(&mut self) -> Result<RegisterSnapshot, Error<T::Error>> {
+            Ok(RegisterSnapshot {
+                measurement_period: self.get_measurement_period().await?,
+                measurement_mode: self.get_measurement_mode().await?,
+                interrupt_config: self.get_interrupt_config().await?,
+                alarm_threshold: self.get_alarm_threshold().await?,
+                pressure: self.get_pressure_compensation().await?,
+                calibration_reference: self.get_calibration_reference().await?,
+            })
+        }
+
+        /// Write all configuration registers of a [RegisterSnapshot] to the sensor
+        ///
+        /// The sensor is put into idle mode first and the measurement mode is written last, so a
+        /// continuous measurement restarts with the restored configuration. A single shot
+        /// measurement is not restarted, the sensor stays idle instead.
+        pub async fn restore_snapshot(
+            &mut self,
+            saved: &RegisterSnapshot,
+        ) -> Result<(), Error<T::Error>> {
+            // The measurement period must only be changed in idle mode
+            self.set_idle().await?;
+
+            self.set_measurement_period(saved.measurement_period)
+                .await?;
+            self.set_interrupt_config(saved.interrupt_config).await?;
+            self.set_alarm_threshold(saved.alarm_threshold).await?;
+            self.set_pressure_compensation(saved.pressure).await?;
+            self.set_calibration_reference(saved.calibration_reference)
+                .await?;
+
+            let mut mode = saved.measurement_mode;
+            if mode.operating_mode == OperatingMode::SingleShot {
+                mode.operating_mode = OperatingMode::Idle;
+            }
+            self.set_measurement_mode(mode).await
+        }
+    }
+}
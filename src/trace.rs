@@ -0,0 +1,497 @@
+//! Recording and replaying I2C traffic
+//!
+//! [Recorder] wraps a bus and records every transfer, including its result, into a fixed-size
+//! buffer. The recorded [Trace] is a compact byte string that can be dumped from a device (e.g.
+//! over defmt or UART) and later fed to [Replay], which drives
+//! [PasCo2](crate::PasCo2) from the trace to reproduce a problem in a host test.
+//!
+//! ```
+//! # use pas_co2_rs::simulator::Simulator;
+//! use pas_co2_rs::blocking::PasCo2;
+//! use pas_co2_rs::trace::{Recorder, Replay};
+//!
+//! # let i2c = Simulator::new();
+//! let mut recorder = Recorder::<_, 256>::new(i2c);
+//! let mut pas_co2 = PasCo2::new(&mut recorder);
+//! let status = pas_co2.get_status().unwrap();
+//!
+//! let mut replay = PasCo2::new(Replay::new(recorder.trace()));
+//! assert_eq!(replay.get_status().unwrap(), status);
+//! ```
+//!
+//! # Format
+//!
+//! A trace is a sequence of entries, one per transfer:
+//!
+//! | Byte(s)       | Content                                                          |
+//! |---------------|------------------------------------------------------------------|
+//! | 0             | Bits 7:6: [TraceOp] (0: write, 1: read, 2: write-read)           |
+//! |               | Bits 2:0: result (0: ok, 1: bus, 2: arbitration loss,            |
+//! |               | 3/4/5: no acknowledge of address/data/unknown, 6: overrun, 7: other) |
+//! | 1             | 7-bit address                                                    |
+//! | 2, 3..        | Length and bytes written, only for write and write-read          |
+//! | next, next..  | Length and bytes read, only for read and write-read              |
+//!
+//! A register access of the driver, e.g. reading the two CO2 bytes, takes 7 bytes.
+
+use embedded_hal::i2c::{
+    Error, ErrorKind, ErrorType, NoAcknowledgeSource, Operation, SevenBitAddress,
+};
+
+const OP_SHIFT: u8 = 6;
+const RESULT_MASK: u8 = 0b111;
+
+/// Kind of a recorded transfer
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TraceOp {
+    /// Single write, e.g. writing a register
+    Write = 0,
+    /// Single read
+    Read = 1,
+    /// Write followed by a read with repeated start, e.g. reading a register
+    WriteRead = 2,
+}
+
+impl TraceOp {
+    fn has_write(self) -> bool {
+        matches!(self, TraceOp::Write | TraceOp::WriteRead)
+    }
+
+    fn has_read(self) -> bool {
+        matches!(self, TraceOp::Read | TraceOp::WriteRead)
+    }
+
+    /// Operation of a transaction, `None` if it is not a single transfer
+    fn of(operations: &[Operation<'_>]) -> Option<Self> {
+        match operations {
+            [Operation::Write(_)] => Some(TraceOp::Write),
+            [Operation::Read(_)] => Some(TraceOp::Read),
+            [Operation::Write(_), Operation::Read(_)] => Some(TraceOp::WriteRead),
+            _ => None,
+        }
+    }
+}
+
+/// Recorded transfer, see [Trace::entries()]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TraceEntry<'a> {
+    /// Address the transfer was sent to
+    pub address: u8,
+    pub op: TraceOp,
+    /// Bytes written, starting with the register, empty for [TraceOp::Read]
+    pub write: &'a [u8],
+    /// Bytes read, empty for [TraceOp::Write]
+    pub read: &'a [u8],
+    pub result: Result<(), ErrorKind>,
+}
+
+/// Invalid or truncated entry at byte `offset` of a trace, see [Trace::new()]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TraceFormatError {
+    pub offset: usize,
+}
+
+/// Recorded I2C traffic in the format described in the [module documentation](self)
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Trace<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Trace<'a> {
+    /// Parse a trace, e.g. one obtained from [Trace::as_bytes()] on a device
+    pub fn new(bytes: &'a [u8]) -> Result<Self, TraceFormatError> {
+        let mut offset = 0;
+        while offset < bytes.len() {
+            let (_, len) = parse_entry(&bytes[offset..]).ok_or(TraceFormatError { offset })?;
+            offset += len;
+        }
+        Ok(Self { bytes })
+    }
+
+    /// Serialized trace
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Recorded transfers in chronological order
+    pub fn entries(&self) -> impl Iterator<Item = TraceEntry<'a>> {
+        let mut rest = self.bytes;
+        core::iter::from_fn(move || {
+            let (entry, len) = parse_entry(rest)?;
+            rest = &rest[len..];
+            Some(entry)
+        })
+    }
+}
+
+/// Parse the entry at the start of `bytes`, returning it and its length
+fn parse_entry(bytes: &[u8]) -> Option<(TraceEntry<'_>, usize)> {
+    let (&header, rest) = bytes.split_first()?;
+    let (&address, mut rest) = rest.split_first()?;
+    if header & !(0b11 << OP_SHIFT | RESULT_MASK) != 0 {
+        return None;
+    }
+    let op = match header >> OP_SHIFT {
+        0 => TraceOp::Write,
+        1 => TraceOp::Read,
+        2 => TraceOp::WriteRead,
+        _ => return None,
+    };
+
+    let mut take = |present: bool| -> Option<&[u8]> {
+        if !present {
+            return Some(&[]);
+        }
+        let (&len, tail) = rest.split_first()?;
+        let (data, tail) = tail.split_at_checked(len.into())?;
+        rest = tail;
+        Some(data)
+    };
+    let write = take(op.has_write())?;
+    let read = take(op.has_read())?;
+
+    let entry = TraceEntry {
+        address,
+        op,
+        write,
+        read,
+        result: decode_result(header & RESULT_MASK),
+    };
+    Some((entry, bytes.len() - rest.len()))
+}
+
+fn encode_result(result: Result<(), ErrorKind>) -> u8 {
+    match result {
+        Ok(()) => 0,
+        Err(ErrorKind::Bus) => 1,
+        Err(ErrorKind::ArbitrationLoss) => 2,
+        Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)) => 3,
+        Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data)) => 4,
+        Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Unknown)) => 5,
+        Err(ErrorKind::Overrun) => 6,
+        Err(_) => 7,
+    }
+}
+
+fn decode_result(code: u8) -> Result<(), ErrorKind> {
+    match code {
+        0 => Ok(()),
+        1 => Err(ErrorKind::Bus),
+        2 => Err(ErrorKind::ArbitrationLoss),
+        3 => Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)),
+        4 => Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data)),
+        5 => Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Unknown)),
+        6 => Err(ErrorKind::Overrun),
+        _ => Err(ErrorKind::Other),
+    }
+}
+
+/// I2C bus wrapper recording all transfers into a buffer of `N` bytes
+///
+/// Transactions other than a single write, read or write-read are passed on but not recorded.
+/// Once a transfer does not fit into the buffer anymore, recording stops, so the trace always
+/// starts at the beginning, which is where a [Replay] has to start as well.
+pub struct Recorder<I2C, const N: usize> {
+    i2c: I2C,
+    buf: [u8; N],
+    len: usize,
+    overflowed: bool,
+}
+
+impl<I2C, const N: usize> Recorder<I2C, N> {
+    /// Record the traffic on `i2c`
+    pub fn new(i2c: I2C) -> Self {
+        Self {
+            i2c,
+            buf: [0; N],
+            len: 0,
+            overflowed: false,
+        }
+    }
+
+    /// Traffic recorded so far
+    pub fn trace(&self) -> Trace<'_> {
+        Trace {
+            bytes: &self.buf[..self.len],
+        }
+    }
+
+    /// Whether recording stopped because the buffer was full
+    pub fn is_overflowed(&self) -> bool {
+        self.overflowed
+    }
+
+    /// Discard the recorded traffic and start recording again
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.overflowed = false;
+    }
+
+    /// Release the underlying bus
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+
+    fn record(&mut self, address: u8, operations: &[Operation<'_>], result: Result<(), ErrorKind>) {
+        let Some(op) = TraceOp::of(operations) else {
+            return;
+        };
+        if self.overflowed {
+            return;
+        }
+
+        let (write, read): (&[u8], &[u8]) = match operations {
+            [Operation::Write(w)] => (w, &[]),
+            [Operation::Read(r)] => (&[], r),
+            [Operation::Write(w), Operation::Read(r)] => (w, r),
+            _ => unreachable!(),
+        };
+        let needed = 2
+            + if op.has_write() { 1 + write.len() } else { 0 }
+            + if op.has_read() { 1 + read.len() } else { 0 };
+        if write.len() > u8::MAX.into() || read.len() > u8::MAX.into() || needed > N - self.len {
+            self.overflowed = true;
+            return;
+        }
+
+        let mut push = |bytes: &[u8]| {
+            self.buf[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+            self.len += bytes.len();
+        };
+        push(&[(op as u8) << OP_SHIFT | encode_result(result), address]);
+        if op.has_write() {
+            push(&[write.len() as u8]);
+            push(write);
+        }
+        if op.has_read() {
+            push(&[read.len() as u8]);
+            push(read);
+        }
+    }
+}
+
+impl<I2C: ErrorType, const N: usize> ErrorType for Recorder<I2C, N> {
+    type Error = I2C::Error;
+}
+
+impl<I2C, const N: usize> embedded_hal::i2c::I2c<SevenBitAddress> for Recorder<I2C, N>
+where
+    I2C: embedded_hal::i2c::I2c<SevenBitAddress>,
+{
+    fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let result = self.i2c.transaction(address, operations);
+        self.record(
+            address,
+            operations,
+            result.as_ref().map_err(Error::kind).copied(),
+        );
+        result
+    }
+}
+
+impl<I2C, const N: usize> embedded_hal_async::i2c::I2c<SevenBitAddress> for Recorder<I2C, N>
+where
+    I2C: embedded_hal_async::i2c::I2c<SevenBitAddress>,
+{
+    async fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let result = self.i2c.transaction(address, operations).await;
+        self.record(
+            address,
+            operations,
+            result.as_ref().map_err(Error::kind).copied(),
+        );
+        result
+    }
+}
+
+/// Error of a [Replay]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ReplayError {
+    /// The recorded transfer failed with this error
+    Recorded(ErrorKind),
+    /// The transfer differs from the recorded entry `index` in kind, address, bytes written
+    /// or number of bytes read. The replay does not advance.
+    Mismatch { index: usize },
+    /// All recorded transfers have been replayed
+    EndOfTrace,
+}
+
+impl Error for ReplayError {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            ReplayError::Recorded(kind) => *kind,
+            ReplayError::Mismatch { .. } | ReplayError::EndOfTrace => ErrorKind::Other,
+        }
+    }
+}
+
+/// I2C bus answering transfers from a [Trace]
+///
+/// Each transfer must match the next recorded one and returns its recorded result and bytes
+/// read. Replaying the same sequence of driver calls therefore reproduces the recorded behavior
+/// without the sensor.
+pub struct Replay<'a> {
+    entries: &'a [u8],
+    /// Index of the next entry
+    index: usize,
+}
+
+impl<'a> Replay<'a> {
+    /// Replay `trace` from its beginning
+    pub fn new(trace: Trace<'a>) -> Self {
+        Self {
+            entries: trace.bytes,
+            index: 0,
+        }
+    }
+
+    /// Number of transfers replayed so far
+    pub fn position(&self) -> usize {
+        self.index
+    }
+
+    /// Whether all recorded transfers have been replayed
+    pub fn is_finished(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn replay(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), ReplayError> {
+        let (entry, len) = parse_entry(self.entries).ok_or(ReplayError::EndOfTrace)?;
+        let mismatch = ReplayError::Mismatch { index: self.index };
+        if entry.address != address || TraceOp::of(operations) != Some(entry.op) {
+            return Err(mismatch);
+        }
+
+        let (write, read): (&[u8], &mut [u8]) = match operations {
+            [Operation::Write(w)] => (w, &mut []),
+            [Operation::Read(r)] => (&[], r),
+            [Operation::Write(w), Operation::Read(r)] => (w, r),
+            _ => return Err(mismatch),
+        };
+        if write != entry.write || read.len() != entry.read.len() {
+            return Err(mismatch);
+        }
+        read.copy_from_slice(entry.read);
+
+        self.entries = &self.entries[len..];
+        self.index += 1;
+        entry.result.map_err(ReplayError::Recorded)
+    }
+}
+
+impl ErrorType for Replay<'_> {
+    type Error = ReplayError;
+}
+
+impl embedded_hal::i2c::I2c<SevenBitAddress> for Replay<'_> {
+    fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.replay(address, operations)
+    }
+}
+
+impl embedded_hal_async::i2c::I2c<SevenBitAddress> for Replay<'_> {
+    async fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.replay(address, operations)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encoding() {
+        let mut recorder = Recorder::<(), 32>::new(());
+        let mut buf = [0x01, 0x90];
+        recorder.record(0x28, &[Operation::Write(&[0x04, 0x02])], Ok(()));
+        recorder.record(
+            0x28,
+            &[Operation::Write(&[0x05]), Operation::Read(&mut buf)],
+            Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data)),
+        );
+        recorder.record(0x70, &[Operation::Read(&mut [0x01])], Err(ErrorKind::Other));
+
+        let trace = recorder.trace();
+        assert_eq!(
+            trace.as_bytes(),
+            [
+                0x00, 0x28, 2, 0x04, 0x02, // write
+                0x84, 0x28, 1, 0x05, 2, 0x01, 0x90, // write-read
+                0x47, 0x70, 1, 0x01, // read
+            ]
+        );
+
+        let mut entries = Trace::new(trace.as_bytes()).unwrap().entries();
+        assert_eq!(
+            entries.nth(1),
+            Some(TraceEntry {
+                address: 0x28,
+                op: TraceOp::WriteRead,
+                write: &[0x05],
+                read: &[0x01, 0x90],
+                result: Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data)),
+            })
+        );
+        assert_eq!(entries.next().unwrap().op, TraceOp::Read);
+        assert_eq!(entries.next(), None);
+    }
+
+    #[test]
+    fn test_result_codes() {
+        for code in 0..=RESULT_MASK {
+            assert_eq!(encode_result(decode_result(code)), code);
+        }
+    }
+
+    #[test]
+    fn test_overflow() {
+        let mut recorder = Recorder::<(), 8>::new(());
+        recorder.record(0x28, &[Operation::Write(&[0x04, 0x02])], Ok(()));
+        recorder.record(0x28, &[Operation::Write(&[0x04, 0x02])], Ok(()));
+        assert!(recorder.is_overflowed());
+        // Smaller transfers that would still fit are not recorded after an overflow
+        recorder.record(0x28, &[Operation::Read(&mut [])], Ok(()));
+        assert_eq!(recorder.trace().entries().count(), 1);
+
+        recorder.clear();
+        assert!(!recorder.is_overflowed());
+        assert!(recorder.trace().as_bytes().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_trace() {
+        assert!(Trace::new(&[]).is_ok());
+        assert_eq!(
+            Trace::new(&[0x00, 0x28, 1, 0x04, 0xC0, 0x28]),
+            Err(TraceFormatError { offset: 4 })
+        );
+        assert_eq!(
+            Trace::new(&[0x00, 0x28, 2, 0x04]),
+            Err(TraceFormatError { offset: 0 })
+        );
+        assert_eq!(
+            Trace::new(&[0x08, 0x28, 1, 0x04]),
+            Err(TraceFormatError { offset: 0 })
+        );
+    }
+}
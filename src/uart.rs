@@ -0,0 +1,182 @@
+/// Error of the UART interface
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone)]
+pub enum UartError<E> {
+    /// Error of the underlying serial port
+    Io(E),
+
+    /// The sensor answered a frame with NAK
+    Nack,
+
+    /// The sensor's answer was not a valid frame
+    InvalidResponse,
+}
+
+/// ACK byte sent by the sensor after a successful write
+const ACK: u8 = 0x06;
+/// NAK byte sent by the sensor after a failed write
+const NAK: u8 = 0x15;
+
+/// Write frame `W,<addr>,<data>\n`, address and data as two hex digits each
+fn write_frame(address: u8, data: u8) -> [u8; 8] {
+    let [a1, a0] = to_hex(address);
+    let [d1, d0] = to_hex(data);
+    [b'W', b',', a1, a0, b',', d1, d0, b'\n']
+}
+
+/// Read frame `R,<addr>\n`, address as two hex digits
+fn read_frame(address: u8) -> [u8; 5] {
+    let [a1, a0] = to_hex(address);
+    [b'R', b',', a1, a0, b'\n']
+}
+
+/// Parse the sensor's answer to a read frame: `<data>\n`, data as two hex digits
+fn parse_read_response(response: [u8; 3]) -> Option<u8> {
+    match response {
+        [d1, d0, b'\n'] => Some(from_hex(d1)? << 4 | from_hex(d0)?),
+        _ => None,
+    }
+}
+
+fn to_hex(value: u8) -> [u8; 2] {
+    const DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+    [
+        DIGITS[(value >> 4) as usize],
+        DIGITS[(value & 0x0F) as usize],
+    ]
+}
+
+fn from_hex(digit: u8) -> Option<u8> {
+    match digit {
+        b'0'..=b'9' => Some(digit - b'0'),
+        b'A'..=b'F' => Some(digit - b'A' + 10),
+        b'a'..=b'f' => Some(digit - b'a' + 10),
+        _ => None,
+    }
+}
+
+// See driver.rs on how the async and blocking variants are generated.
+#[maybe_async_cfg::maybe(sync(self = "blocking"), async(self = "asynchronous"))]
+pub mod uart {
+    #[maybe_async_cfg::only_if(async)]
+    use crate::interface::asynchronous::Interface;
+    #[maybe_async_cfg::only_if(sync)]
+    use crate::interface::blocking::Interface;
+    #[maybe_async_cfg::only_if(sync)]
+    use embedded_io::{Read, ReadExactError, Write};
+    #[maybe_async_cfg::only_if(async)]
+    use embedded_io_async::{Read, ReadExactError, Write};
+
+    use super::{parse_read_response, read_frame, write_frame, UartError, ACK, NAK};
+    use crate::regs::Register;
+
+    /// UART [Interface] of the sensor
+    ///
+    /// Implements the sensor's ASCII protocol, which transfers one register byte per frame.
+    /// The serial port must be configured to 9600 baud, 8 data bits, no parity and 1 stop bit.
+    pub struct UartInterface<U> {
+        uart: U,
+    }
+
+    impl<U> UartInterface<U>
+    where
+        U: Read + Write,
+    {
+        /// Create a new UART interface
+        pub fn new(uart: U) -> Self {
+            Self { uart }
+        }
+
+        async fn write_byte(&mut self, address: u8, data: u8) -> Result<(), UartError<U::Error>> {
+            self.uart
+                .write_all(&write_frame(address, data))
+                .await
+                .map_err(UartError::Io)?;
+            self.uart.flush().await.map_err(UartError::Io)?;
+
+            let mut response = [0u8; 1];
+            self.read_response(&mut response).await?;
+            match response[0] {
+                ACK => Ok(()),
+                NAK => Err(UartError::Nack),
+                _ => Err(UartError::InvalidResponse),
+            }
+        }
+
+        async fn read_byte(&mut self, address: u8) -> Result<u8, UartError<U::Error>> {
+            self.uart
+                .write_all(&read_frame(address))
+                .await
+                .map_err(UartError::Io)?;
+            self.uart.flush().await.map_err(UartError::Io)?;
+
+            // The sensor answers with NAK instead of data if it can't process the frame
+            let mut response = [0u8; 3];
+            self.read_response(&mut response[..1]).await?;
+            if response[0] == NAK {
+                return Err(UartError::Nack);
+            }
+            self.read_response(&mut response[1..]).await?;
+            parse_read_response(response).ok_or(UartError::InvalidResponse)
+        }
+
+        async fn read_response(&mut self, buf: &mut [u8]) -> Result<(), UartError<U::Error>> {
+            self.uart.read_exact(buf).await.map_err(|e| match e {
+                ReadExactError::UnexpectedEof => UartError::InvalidResponse,
+                ReadExactError::Other(e) => UartError::Io(e),
+            })
+        }
+    }
+
+    impl<U> Interface for UartInterface<U>
+    where
+        U: Read + Write,
+    {
+        type Error = UartError<U::Error>;
+
+        async fn write_reg(&mut self, reg: Register, val: &[u8]) -> Result<(), Self::Error> {
+            assert!(val.len() <= 2);
+            assert!(!val.is_empty());
+            let address: u8 = reg.into();
+            for (offset, byte) in (0..).zip(val) {
+                self.write_byte(address + offset, *byte).await?;
+            }
+            Ok(())
+        }
+
+        async fn read_reg(&mut self, reg: Register, buf: &mut [u8]) -> Result<(), Self::Error> {
+            let address: u8 = reg.into();
+            for (offset, byte) in (0..).zip(buf.iter_mut()) {
+                *byte = self.read_byte(address + offset).await?;
+            }
+            Ok(())
+        }
+
+        fn is_nack(error: &Self::Error) -> bool {
+            matches!(error, UartError::Nack)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_write_frame() {
+        assert_eq!(&write_frame(0x0B, 0x03), b"W,0B,03\n");
+    }
+
+    #[test]
+    fn test_read_frame() {
+        assert_eq!(&read_frame(0x10), b"R,10\n");
+    }
+
+    #[test]
+    fn test_parse_read_response() {
+        assert_eq!(parse_read_response(*b"A5\n"), Some(0xA5));
+        assert_eq!(parse_read_response(*b"a5\n"), Some(0xA5));
+        assert_eq!(parse_read_response(*b"G5\n"), None);
+        assert_eq!(parse_read_response(*b"A5\r"), None);
+    }
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "cli")]
+
+use std::process::{Command, Output};
+
+/// Run the `pas-co2` binary on a simulated sensor
+fn pas_co2(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_pas-co2"))
+        .arg("--simulate")
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "{output:?}");
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn test_status() {
+    let out = stdout(&pas_co2(&["status"]));
+    assert!(out.contains("product: 2"));
+    assert!(out.contains("ready: true"));
+}
+
+#[test]
+fn test_read() {
+    assert_eq!(stdout(&pas_co2(&["read"])), "400 ppm\n");
+}
+
+#[test]
+fn test_monitor() {
+    let out = stdout(&pas_co2(&["monitor", "--period", "5", "--count", "3"]));
+    assert_eq!(out.lines().count(), 3);
+    assert!(out.lines().all(|line| line == "400 ppm"));
+}
+
+#[test]
+fn test_dump() {
+    let out = stdout(&pas_co2(&["dump"]));
+    assert!(out.contains("RegisterSnapshot"));
+    assert!(out.contains("PressureHpa(\n        1015"));
+}
+
+#[test]
+fn test_set_pressure() {
+    assert!(stdout(&pas_co2(&["set-pressure", "950"])).contains("950 hPa"));
+    assert!(!pas_co2(&["set-pressure", "600"]).status.success());
+}
+
+#[test]
+fn test_calibrate() {
+    let out = stdout(&pas_co2(&["calibrate", "--reference", "420", "--save"]));
+    assert!(out.contains("[400, 400, 400]"));
+    assert!(!pas_co2(&["calibrate", "--reference", "1000"])
+        .status
+        .success());
+}
+
+#[test]
+fn test_reset() {
+    stdout(&pas_co2(&["reset", "soft"]));
+    stdout(&pas_co2(&["reset", "enable-iir"]));
+    assert!(!pas_co2(&["reset", "unknown"]).status.success());
+}
+
+#[test]
+fn test_missing_bus() {
+    let output = Command::new(env!("CARGO_BIN_EXE_pas-co2"))
+        .args(["--bus", "/dev/does-not-exist", "read"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("/dev/does-not-exist"));
+}
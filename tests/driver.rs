@@ -0,0 +1,1100 @@
+use embassy_futures::block_on;
+use pas_co2_rs::filter::{Exponential, Filter, Median, MovingAverage};
+use pas_co2_rs::regs::*;
+use pas_co2_rs::simulator::{Simulator, MEASUREMENT_DURATION_MS, STARTUP_DURATION_MS};
+use pas_co2_rs::{
+    Error, ForcedCompensationConfig, InitError, InitStep, PasCo2, ResponseError, SensorFault,
+    SnapshotDiff,
+};
+
+fn setup() -> (Simulator, PasCo2<pas_co2_rs::I2cInterface<Simulator>>) {
+    let sim = Simulator::new();
+    let pas_co2 = PasCo2::new(sim.clone());
+    (sim, pas_co2)
+}
+
+#[test]
+fn test_get_status() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        let status = pas_co2.get_status().await.unwrap();
+        assert!(status.ready);
+        assert!(!status.temperature_error);
+
+        sim.raise_temperature_error();
+        assert!(pas_co2.get_status().await.unwrap().temperature_error);
+    });
+}
+
+#[test]
+fn test_clear_status() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        sim.raise_temperature_error();
+        sim.raise_voltage_error();
+        pas_co2
+            .set_alarm_threshold(AlarmThresholdPpm::new(1000).unwrap())
+            .await
+            .unwrap();
+        // Invalid operating mode raises a communication error
+        pas_co2.set_measurement_mode(0x03.into()).await.unwrap();
+
+        let status = pas_co2.get_status().await.unwrap();
+        assert!(status.temperature_error && status.voltage_error && status.communication_error);
+
+        pas_co2.clear_status().await.unwrap();
+        let status = pas_co2.get_status().await.unwrap();
+        assert!(!status.temperature_error && !status.voltage_error && !status.communication_error);
+        assert!(status.ready);
+    });
+}
+
+#[test]
+fn test_clear_individual_errors() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        sim.raise_temperature_error();
+        sim.raise_voltage_error();
+        pas_co2.set_measurement_mode(0x03.into()).await.unwrap();
+
+        pas_co2.clear_temperature_error().await.unwrap();
+        let status = pas_co2.get_status().await.unwrap();
+        assert!(!status.temperature_error && status.voltage_error && status.communication_error);
+
+        pas_co2.clear_voltage_error().await.unwrap();
+        let status = pas_co2.get_status().await.unwrap();
+        assert!(!status.voltage_error && status.communication_error);
+
+        pas_co2.clear_communication_error().await.unwrap();
+        assert!(!pas_co2.get_status().await.unwrap().communication_error);
+    });
+}
+
+#[test]
+fn test_measurement_period() {
+    let (_sim, mut pas_co2) = setup();
+    block_on(async {
+        assert_eq!(pas_co2.get_measurement_period().await.unwrap().get(), 60);
+        pas_co2
+            .set_measurement_period(MeasurementPeriod::new(4095).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(pas_co2.get_measurement_period().await.unwrap().get(), 4095);
+        pas_co2
+            .set_measurement_period(MeasurementPeriod::new(5).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(pas_co2.get_measurement_period().await.unwrap().get(), 5);
+    });
+}
+
+#[test]
+fn test_measurement_mode() {
+    let (_sim, mut pas_co2) = setup();
+    block_on(async {
+        let mode = pas_co2.get_measurement_mode().await.unwrap();
+        assert_eq!(u8::from(mode), u8::from(MeasurementMode::default()));
+
+        let mode = MeasurementMode {
+            pwm_out_enable: false,
+            pwm_mode: PwmMode::PulseTrain,
+            baseline_offset_comp: BaselineOffsetCompensation::Disabled,
+            operating_mode: OperatingMode::Idle,
+        };
+        pas_co2.set_measurement_mode(mode).await.unwrap();
+        let read = pas_co2.get_measurement_mode().await.unwrap();
+        assert_eq!(u8::from(read), 0b0001_0000);
+    });
+}
+
+#[test]
+fn test_single_shot_measurement() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        sim.set_co2_ppm(612);
+        pas_co2.start_measurement().await.unwrap();
+        assert!(!pas_co2.get_measurement_status().await.unwrap().data_ready);
+
+        sim.advance_ms(MEASUREMENT_DURATION_MS);
+        assert!(pas_co2.get_measurement_status().await.unwrap().data_ready);
+        assert_eq!(pas_co2.get_co2_ppm().await.unwrap(), 612);
+
+        // Reading the status cleared the data ready bit, the sensor is back in idle
+        assert!(!pas_co2.get_measurement_status().await.unwrap().data_ready);
+        let mode = pas_co2.get_measurement_mode().await.unwrap();
+        assert!(matches!(mode.operating_mode, OperatingMode::Idle));
+    });
+}
+
+#[test]
+fn test_measure_single_shot() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        sim.set_co2_ppm(935);
+        let co2_ppm = pas_co2
+            .measure_single_shot(sim.delay(), 2000)
+            .await
+            .unwrap();
+        assert_eq!(co2_ppm, 935);
+        assert!(sim.now_ms() >= MEASUREMENT_DURATION_MS);
+
+        let status = pas_co2.get_measurement_status().await.unwrap();
+        assert!(!status.data_ready && !status.int_active && !status.alarm);
+    });
+}
+
+#[test]
+fn test_measure_single_shot_timeout() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        let result = pas_co2.measure_single_shot(sim.delay(), 500).await;
+        assert!(matches!(result, Err(Error::Timeout)));
+        assert_eq!(sim.now_ms(), 500);
+    });
+}
+
+#[test]
+fn test_continuous_measurement() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        pas_co2
+            .set_measurement_period(MeasurementPeriod::new(5).unwrap())
+            .await
+            .unwrap();
+        pas_co2
+            .set_measurement_mode(MeasurementMode {
+                operating_mode: OperatingMode::Continuous,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        sim.set_co2_ppm(500);
+        sim.advance_ms(MEASUREMENT_DURATION_MS);
+        assert!(pas_co2.get_measurement_status().await.unwrap().data_ready);
+        assert_eq!(pas_co2.get_co2_ppm().await.unwrap(), 500);
+
+        sim.set_co2_ppm(510);
+        sim.advance_ms(5000);
+        assert!(pas_co2.get_measurement_status().await.unwrap().data_ready);
+        assert_eq!(pas_co2.get_co2_ppm().await.unwrap(), 510);
+    });
+}
+
+#[test]
+fn test_alarm_and_measurement_status() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        pas_co2
+            .set_interrupt_config(InterruptConfig {
+                int_function_config: IntFunctionConfig::Alarm,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        pas_co2
+            .set_alarm_threshold(AlarmThresholdPpm::new(1000).unwrap())
+            .await
+            .unwrap();
+
+        sim.set_co2_ppm(800);
+        pas_co2.start_measurement().await.unwrap();
+        sim.advance_ms(MEASUREMENT_DURATION_MS);
+
+        sim.set_co2_ppm(1200);
+        pas_co2.start_measurement().await.unwrap();
+        sim.advance_ms(MEASUREMENT_DURATION_MS);
+
+        let status = pas_co2.get_measurement_status().await.unwrap();
+        assert!(status.alarm && status.int_active);
+        assert!(sim.int_pin_active());
+
+        pas_co2.clear_int_active().await.unwrap();
+        let status = pas_co2.get_measurement_status().await.unwrap();
+        assert!(status.alarm && !status.int_active);
+
+        pas_co2.clear_alarm().await.unwrap();
+        assert!(!pas_co2.get_measurement_status().await.unwrap().alarm);
+    });
+}
+
+#[test]
+fn test_clear_measurement_status() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        pas_co2
+            .set_interrupt_config(InterruptConfig {
+                int_function_config: IntFunctionConfig::Alarm,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        pas_co2
+            .set_alarm_threshold(AlarmThresholdPpm::new(1000).unwrap())
+            .await
+            .unwrap();
+        for ppm in [800, 1200] {
+            sim.set_co2_ppm(ppm);
+            pas_co2.start_measurement().await.unwrap();
+            sim.advance_ms(MEASUREMENT_DURATION_MS);
+        }
+
+        pas_co2.clear_measurement_status().await.unwrap();
+        let status = pas_co2.get_measurement_status().await.unwrap();
+        assert!(!status.alarm && !status.int_active);
+    });
+}
+
+#[test]
+fn test_interrupt_config() {
+    let (_sim, mut pas_co2) = setup();
+    block_on(async {
+        assert_eq!(
+            pas_co2.get_interrupt_config().await.unwrap(),
+            InterruptConfig::default()
+        );
+
+        let config = InterruptConfig {
+            int_pin_active_high: false,
+            int_function_config: IntFunctionConfig::DataReady,
+            alarm_crossing_up: false,
+        };
+        pas_co2.set_interrupt_config(config).await.unwrap();
+        assert_eq!(pas_co2.get_interrupt_config().await.unwrap(), config);
+    });
+}
+
+#[test]
+fn test_invalid_interrupt_config() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        sim.poke(Register::InterruptConfig as u8, 0b0000_1110);
+        assert!(matches!(
+            pas_co2.get_interrupt_config().await,
+            Err(Error::Response(ResponseError::InvalidRegisterValue))
+        ));
+    });
+}
+
+#[test]
+fn test_alarm_threshold() {
+    let (_sim, mut pas_co2) = setup();
+    block_on(async {
+        assert_eq!(pas_co2.get_alarm_threshold().await.unwrap().get(), 0);
+        pas_co2
+            .set_alarm_threshold(AlarmThresholdPpm::new(1400).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(pas_co2.get_alarm_threshold().await.unwrap().get(), 1400);
+    });
+}
+
+#[test]
+fn test_pressure_compensation() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        assert_eq!(
+            pas_co2.get_pressure_compensation().await.unwrap().get(),
+            1015
+        );
+        pas_co2
+            .set_pressure_compensation(PressureHpa::new(950).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(
+            pas_co2.get_pressure_compensation().await.unwrap().get(),
+            950
+        );
+        assert!(!pas_co2.get_status().await.unwrap().communication_error);
+        assert_eq!(sim.peek(Register::PressureReference as u8), 0x03);
+    });
+}
+
+#[test]
+fn test_pressure_compensation_from_altitude() {
+    let (_sim, mut pas_co2) = setup();
+    block_on(async {
+        let pressure = pas_co2
+            .set_pressure_compensation_from_altitude(1000.0, None)
+            .await
+            .unwrap();
+        assert_eq!(pressure.get(), 899);
+        assert_eq!(pas_co2.get_pressure_compensation().await.unwrap(), pressure);
+
+        let pressure = pas_co2
+            .set_pressure_compensation_from_altitude(1000.0, Some(1030.0))
+            .await
+            .unwrap();
+        assert_eq!(pressure.get(), 914);
+
+        // Too high for the sensor, the previous value is kept
+        assert!(matches!(
+            pas_co2
+                .set_pressure_compensation_from_altitude(3000.0, None)
+                .await,
+            Err(Error::OutOfRange)
+        ));
+        assert_eq!(pas_co2.get_pressure_compensation().await.unwrap(), pressure);
+    });
+}
+
+#[test]
+fn test_co2_mass_concentration() {
+    let (_sim, mut pas_co2) = setup();
+    block_on(async {
+        pas_co2
+            .set_pressure_compensation(PressureHpa::new(900).unwrap())
+            .await
+            .unwrap();
+        let concentration = pas_co2.co2_mass_concentration(1000, 2000).await.unwrap();
+        assert_eq!(concentration.ug_per_m3(), 1_625_043);
+        assert_eq!(concentration.mg_per_m3(), 1625);
+
+        assert!(matches!(
+            pas_co2.co2_mass_concentration(1000, -20_000).await,
+            Err(Error::OutOfRange)
+        ));
+    });
+}
+
+#[test]
+fn test_out_of_range_register_value() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        // 0x8000 is a negative threshold
+        sim.poke(Register::AlarmThreshold as u8, 0x80);
+        assert!(matches!(
+            pas_co2.get_alarm_threshold().await,
+            Err(Error::Response(ResponseError::InvalidRegisterValue))
+        ));
+    });
+}
+
+#[test]
+fn test_out_of_range_error() {
+    let (_, mut pas_co2) = setup();
+    block_on(async {
+        let result = pas_co2
+            .set_pressure_compensation_from_altitude(10_000.0, None)
+            .await;
+        assert!(matches!(result, Err(Error::OutOfRange)));
+    });
+}
+
+#[test]
+fn test_interface_error_conversion() {
+    fn transfer() -> Result<(), Error<u8>> {
+        Err(0x42)?
+    }
+
+    assert!(matches!(transfer(), Err(Error::Interface(0x42))));
+}
+
+#[test]
+fn test_forced_compensation() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        sim.set_co2_ppm(480);
+        let config = ForcedCompensationConfig::new(CalibrationPpm::new(490).unwrap());
+        let report = pas_co2
+            .do_forced_compensation(config, sim.delay())
+            .await
+            .unwrap();
+        assert_eq!(report.readings(), &[480, 480, 480]);
+
+        // The previous measurement period and mode are restored
+        let mode = pas_co2.get_measurement_mode().await.unwrap();
+        assert_eq!(mode, MeasurementMode::default());
+        assert_eq!(pas_co2.get_measurement_period().await.unwrap().get(), 60);
+        assert_eq!(
+            pas_co2.get_calibration_reference().await.unwrap().get(),
+            490
+        );
+        assert!(!sim.forced_calibration_saved());
+    });
+}
+
+#[test]
+fn test_forced_compensation_config() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        let previous_mode = MeasurementMode {
+            operating_mode: OperatingMode::Continuous,
+            ..Default::default()
+        };
+        pas_co2
+            .set_measurement_period(MeasurementPeriod::new(30).unwrap())
+            .await
+            .unwrap();
+        pas_co2.set_measurement_mode(previous_mode).await.unwrap();
+
+        let config = ForcedCompensationConfig {
+            iterations: 5,
+            period: MeasurementPeriod::MIN,
+            timeout_ms: 30_000,
+            save_to_nvm: true,
+            ..ForcedCompensationConfig::new(CalibrationPpm::new(400).unwrap())
+        };
+        let start_ms = sim.now_ms();
+        let report = pas_co2
+            .do_forced_compensation(config, sim.delay())
+            .await
+            .unwrap();
+        assert_eq!(report.readings().len(), 5);
+        assert!(sim.now_ms() - start_ms <= 5 * 5000 + MEASUREMENT_DURATION_MS);
+        assert!(sim.forced_calibration_saved());
+
+        assert_eq!(pas_co2.get_measurement_mode().await.unwrap(), previous_mode);
+        assert_eq!(pas_co2.get_measurement_period().await.unwrap().get(), 30);
+    });
+}
+
+#[test]
+fn test_forced_compensation_timeout() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        let config = ForcedCompensationConfig {
+            timeout_ms: 15_000,
+            ..ForcedCompensationConfig::new(CalibrationPpm::new(400).unwrap())
+        };
+        assert!(matches!(
+            pas_co2.do_forced_compensation(config, sim.delay()).await,
+            Err(Error::Timeout)
+        ));
+
+        // The previous state is restored on errors as well
+        let mode = pas_co2.get_measurement_mode().await.unwrap();
+        assert_eq!(mode, MeasurementMode::default());
+        assert_eq!(pas_co2.get_measurement_period().await.unwrap().get(), 60);
+
+        let config = ForcedCompensationConfig {
+            iterations: 0,
+            ..config
+        };
+        assert!(matches!(
+            pas_co2.do_forced_compensation(config, sim.delay()).await,
+            Err(Error::OutOfRange)
+        ));
+    });
+}
+
+#[test]
+fn test_calibration_reference() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        assert_eq!(
+            pas_co2.get_calibration_reference().await.unwrap().get(),
+            400
+        );
+        pas_co2
+            .set_calibration_reference(CalibrationPpm::new(450).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(
+            pas_co2.get_calibration_reference().await.unwrap().get(),
+            450
+        );
+        assert_eq!(sim.peek(Register::CalibrationReference as u8), 0x01);
+        assert_eq!(sim.peek(Register::CalibrationReference as u8 + 1), 0xC2);
+    });
+}
+
+#[test]
+fn test_calibration_reference_independent_of_pressure() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        pas_co2
+            .set_pressure_compensation(PressureHpa::new(950).unwrap())
+            .await
+            .unwrap();
+        pas_co2
+            .set_calibration_reference(CalibrationPpm::new(450).unwrap())
+            .await
+            .unwrap();
+        pas_co2
+            .do_forced_compensation(
+                ForcedCompensationConfig::new(CalibrationPpm::new(490).unwrap()),
+                sim.delay(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            pas_co2.get_pressure_compensation().await.unwrap().get(),
+            950
+        );
+        assert_eq!(
+            pas_co2.get_calibration_reference().await.unwrap().get(),
+            490
+        );
+        assert!(!pas_co2.get_status().await.unwrap().communication_error);
+    });
+}
+
+#[test]
+fn test_write_read() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        assert_eq!(
+            pas_co2.test_write_read(0b1010_0101).await.unwrap(),
+            0b1010_0101
+        );
+        assert_eq!(sim.peek(Register::ScratchPad as u8), 0b1010_0101);
+    });
+}
+
+#[test]
+fn test_soft_reset() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        pas_co2
+            .set_alarm_threshold(AlarmThresholdPpm::new(1400).unwrap())
+            .await
+            .unwrap();
+        pas_co2.soft_reset(SoftReset::SoftReset).await.unwrap();
+        assert_eq!(pas_co2.get_alarm_threshold().await.unwrap().get(), 0);
+
+        assert_eq!(pas_co2.iir_filter_enabled(), None);
+        pas_co2
+            .soft_reset(SoftReset::DisableStepwiseReractiveIirFilter)
+            .await
+            .unwrap();
+        assert!(!sim.iir_filter_enabled());
+        assert_eq!(pas_co2.iir_filter_enabled(), Some(false));
+        pas_co2
+            .soft_reset(SoftReset::EnableStepwiseReaciveIirFilter)
+            .await
+            .unwrap();
+        assert!(sim.iir_filter_enabled());
+        assert_eq!(pas_co2.iir_filter_enabled(), Some(true));
+
+        pas_co2
+            .soft_reset(SoftReset::SaveForceCalibNvm)
+            .await
+            .unwrap();
+        assert!(sim.forced_calibration_saved());
+    });
+}
+
+#[test]
+fn test_probe() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        let id = pas_co2.probe().await.unwrap();
+        assert_eq!(id.product, ProductId::PAS_CO2);
+        assert_eq!(id.revision, 2);
+
+        sim.poke(Register::ProdId as u8, 0b1100_0010);
+        assert_eq!(pas_co2.identify().await.unwrap().product, 0b110);
+        assert!(matches!(
+            pas_co2.probe().await,
+            Err(Error::UnknownDevice(ProductId {
+                product: 0b110,
+                revision: 2
+            }))
+        ));
+    });
+}
+
+#[test]
+fn test_probe_without_sensor() {
+    let (sim, mut pas_co2) = setup();
+    sim.inject_nacks(1);
+    assert!(matches!(
+        block_on(pas_co2.probe()),
+        Err(Error::Interface(_))
+    ));
+}
+
+#[test]
+fn test_snapshot_restore_after_soft_reset() {
+    let (_sim, mut pas_co2) = setup();
+    block_on(async {
+        let defaults = pas_co2.read_snapshot().await.unwrap();
+
+        pas_co2
+            .set_measurement_period(MeasurementPeriod::new(30).unwrap())
+            .await
+            .unwrap();
+        pas_co2
+            .set_alarm_threshold(AlarmThresholdPpm::new(1200).unwrap())
+            .await
+            .unwrap();
+        pas_co2
+            .set_pressure_compensation(PressureHpa::new(950).unwrap())
+            .await
+            .unwrap();
+        pas_co2
+            .set_calibration_reference(CalibrationPpm::new(420).unwrap())
+            .await
+            .unwrap();
+        pas_co2
+            .set_interrupt_config(InterruptConfig {
+                int_pin_active_high: false,
+                int_function_config: IntFunctionConfig::Alarm,
+                alarm_crossing_up: true,
+            })
+            .await
+            .unwrap();
+        let configured = pas_co2.read_snapshot().await.unwrap();
+
+        let diff = defaults.diff(&configured);
+        assert!(!diff.is_empty());
+        assert_eq!(
+            diff,
+            SnapshotDiff {
+                measurement_period: true,
+                measurement_mode: false,
+                interrupt_config: true,
+                alarm_threshold: true,
+                pressure: true,
+                calibration_reference: true,
+            }
+        );
+
+        pas_co2.soft_reset(SoftReset::SoftReset).await.unwrap();
+        assert_eq!(pas_co2.read_snapshot().await.unwrap(), defaults);
+
+        pas_co2.restore_snapshot(&configured).await.unwrap();
+        let restored = pas_co2.read_snapshot().await.unwrap();
+        assert_eq!(restored, configured);
+        assert!(restored.diff(&configured).is_empty());
+        assert!(!pas_co2.get_status().await.unwrap().communication_error);
+    });
+}
+
+#[test]
+fn test_snapshot_restore_measurement_mode() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        let mut snapshot = pas_co2.read_snapshot().await.unwrap();
+        snapshot.measurement_period = MeasurementPeriod::MIN;
+        snapshot.measurement_mode.operating_mode = OperatingMode::Continuous;
+        pas_co2.restore_snapshot(&snapshot).await.unwrap();
+        assert_eq!(pas_co2.read_snapshot().await.unwrap(), snapshot);
+
+        // A single shot measurement is not restarted
+        snapshot.measurement_mode.operating_mode = OperatingMode::SingleShot;
+        pas_co2.restore_snapshot(&snapshot).await.unwrap();
+        let mode = pas_co2.get_measurement_mode().await.unwrap();
+        assert_eq!(mode.operating_mode, OperatingMode::Idle);
+
+        sim.advance_ms(MEASUREMENT_DURATION_MS);
+        assert!(!pas_co2.get_measurement_status().await.unwrap().data_ready);
+    });
+}
+
+#[test]
+fn test_init_after_power_on() {
+    let sim = Simulator::power_on();
+    let mut pas_co2 = PasCo2::new(sim.clone());
+    block_on(async {
+        assert!(!pas_co2.get_status().await.unwrap().ready);
+
+        // Writes before the sensor is ready are lost
+        pas_co2
+            .set_alarm_threshold(AlarmThresholdPpm::new(1000).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(pas_co2.get_alarm_threshold().await.unwrap().get(), 0);
+
+        sim.inject_nacks(2);
+        pas_co2.init(sim.delay(), 1000).await.unwrap();
+        assert!(sim.now_ms() >= STARTUP_DURATION_MS);
+        assert!(pas_co2.get_status().await.unwrap().ready);
+
+        pas_co2
+            .set_alarm_threshold(AlarmThresholdPpm::new(1000).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(pas_co2.get_alarm_threshold().await.unwrap().get(), 1000);
+    });
+}
+
+#[test]
+fn test_init_clears_errors() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        sim.raise_temperature_error();
+        sim.raise_voltage_error();
+        pas_co2.init(sim.delay(), 1000).await.unwrap();
+
+        let status = pas_co2.get_status().await.unwrap();
+        assert!(!status.temperature_error);
+        assert!(!status.voltage_error);
+        assert!(!status.communication_error);
+    });
+}
+
+#[test]
+fn test_init_timeout() {
+    let sim = Simulator::power_on();
+    let mut pas_co2 = PasCo2::new(sim.clone());
+    let result = block_on(pas_co2.init(sim.delay(), STARTUP_DURATION_MS as u32 / 2));
+    assert!(matches!(
+        result,
+        Err(InitError {
+            step: InitStep::WaitReady,
+            error: Error::Timeout
+        })
+    ));
+}
+
+#[test]
+fn test_fault_checking() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        sim.raise_voltage_error();
+        assert!(pas_co2.measure_single_shot(sim.delay(), 2000).await.is_ok());
+
+        pas_co2.set_fault_checking(true);
+        assert!(matches!(
+            pas_co2.measure_single_shot(sim.delay(), 2000).await,
+            Err(Error::Sensor(SensorFault::Voltage))
+        ));
+
+        sim.raise_temperature_error();
+        assert!(matches!(
+            pas_co2.get_co2_ppm().await,
+            Err(Error::Sensor(SensorFault::Temperature))
+        ));
+
+        pas_co2.clear_status().await.unwrap();
+        assert!(pas_co2.measure_single_shot(sim.delay(), 2000).await.is_ok());
+    });
+}
+
+#[test]
+fn test_fault_checking_continuous() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        pas_co2.set_fault_checking(true);
+        let mut readings = pas_co2
+            .start_continuous(MeasurementPeriod::MIN, sim.delay())
+            .await
+            .unwrap();
+        assert!(readings.next_reading().await.is_ok());
+
+        // Ready with a communication error
+        sim.poke(Register::SensorStatus as u8, 0b1000_1000);
+        assert!(matches!(
+            readings.next_reading().await,
+            Err(Error::Sensor(SensorFault::Communication))
+        ));
+    });
+}
+
+#[test]
+fn test_nack_is_interface_error() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        sim.inject_nacks(1);
+        assert!(matches!(
+            pas_co2.get_status().await,
+            Err(Error::Interface(
+                embedded_hal::i2c::ErrorKind::NoAcknowledge(_)
+            ))
+        ));
+        assert!(pas_co2.get_status().await.is_ok());
+    });
+}
+
+#[test]
+fn test_blocking_driver() {
+    let sim = Simulator::new();
+    let mut pas_co2 = pas_co2_rs::blocking::PasCo2::new(sim.clone());
+
+    sim.set_co2_ppm(742);
+    pas_co2
+        .set_pressure_compensation(PressureHpa::new(980).unwrap())
+        .unwrap();
+    assert_eq!(pas_co2.get_pressure_compensation().unwrap().get(), 980);
+
+    pas_co2.start_measurement().unwrap();
+    sim.advance_ms(MEASUREMENT_DURATION_MS);
+    assert!(pas_co2.get_measurement_status().unwrap().data_ready);
+    assert_eq!(pas_co2.get_co2_ppm().unwrap(), 742);
+
+    sim.set_co2_ppm(811);
+    assert_eq!(pas_co2.measure_single_shot(sim.delay(), 2000).unwrap(), 811);
+
+    pas_co2
+        .do_forced_compensation(
+            ForcedCompensationConfig::new(CalibrationPpm::new(490).unwrap()),
+            sim.delay(),
+        )
+        .unwrap();
+    assert!(matches!(
+        pas_co2.get_measurement_mode().unwrap().operating_mode,
+        OperatingMode::Idle
+    ));
+}
+
+#[test]
+fn test_continuous_readings() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        sim.set_co2_ppm(420);
+        let mut readings = pas_co2
+            .start_continuous(MeasurementPeriod::MIN, sim.delay())
+            .await
+            .unwrap();
+        assert_eq!(readings.next_reading().await.unwrap(), 420);
+        let first = sim.now_ms();
+
+        sim.set_co2_ppm(430);
+        assert_eq!(readings.next_reading().await.unwrap(), 430);
+        // The second reading is only returned once it is new
+        assert!(sim.now_ms() - first >= 5000);
+
+        readings.stop().await.unwrap();
+        let mode = pas_co2.get_measurement_mode().await.unwrap();
+        assert!(matches!(mode.operating_mode, OperatingMode::Idle));
+        assert_eq!(pas_co2.get_measurement_period().await.unwrap().get(), 5);
+    });
+}
+
+#[test]
+fn test_continuous_readings_error_per_item() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        sim.set_co2_ppm(455);
+        let mut readings = pas_co2
+            .start_continuous(MeasurementPeriod::MIN, sim.delay())
+            .await
+            .unwrap();
+
+        // A sensor that does not respond makes the current reading time out
+        sim.inject_nacks(usize::MAX);
+        assert!(matches!(readings.next_reading().await, Err(Error::Timeout)));
+
+        // Once it responds again, the readings continue
+        sim.inject_nacks(0);
+        assert_eq!(readings.next_reading().await.unwrap(), 455);
+    });
+}
+
+#[test]
+fn test_continuous_readings_idle_after_drop() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        let mut readings = pas_co2
+            .start_continuous(MeasurementPeriod::MIN, sim.delay())
+            .await
+            .unwrap();
+        readings.next_reading().await.unwrap();
+        drop(readings);
+
+        let mode = pas_co2.get_measurement_mode().await.unwrap();
+        assert!(matches!(mode.operating_mode, OperatingMode::Idle));
+
+        // No more measurements are taken
+        sim.advance_ms(10_000);
+        assert!(!pas_co2.get_measurement_status().await.unwrap().data_ready);
+    });
+}
+
+#[test]
+fn test_filtered_readings() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        let mut readings = pas_co2
+            .start_continuous(MeasurementPeriod::MIN, sim.delay())
+            .await
+            .unwrap()
+            .with_filter(Median::<3>::new())
+            .await
+            .unwrap();
+        // The sensor's filter is disabled to not filter twice
+        assert!(!sim.iir_filter_enabled());
+
+        for (co2_ppm, filtered) in [(400, 400), (410, 405), (2000, 410), (420, 420)] {
+            sim.set_co2_ppm(co2_ppm);
+            assert_eq!(readings.next_reading().await.unwrap(), filtered);
+        }
+
+        readings.filter().reset();
+        sim.set_co2_ppm(600);
+        assert_eq!(readings.next_reading().await.unwrap(), 600);
+        readings.stop().await.unwrap();
+
+        // Stopping enables the sensor's filter again
+        assert_eq!(pas_co2.iir_filter_enabled(), Some(true));
+        assert!(sim.iir_filter_enabled());
+        let mode = pas_co2.get_measurement_mode().await.unwrap();
+        assert!(matches!(mode.operating_mode, OperatingMode::Idle));
+    });
+}
+
+#[test]
+fn test_filtered_readings_iir_filter_after_drop() {
+    let (sim, mut pas_co2) = setup();
+    block_on(async {
+        let mut readings = pas_co2
+            .start_continuous(MeasurementPeriod::MIN, sim.delay())
+            .await
+            .unwrap()
+            .with_filter(MovingAverage::<2>::new())
+            .await
+            .unwrap();
+        readings.next_reading().await.unwrap();
+        drop(readings);
+
+        // The async driver enables the filter again before its next transfer
+        assert!(!sim.iir_filter_enabled());
+        pas_co2.get_status().await.unwrap();
+        assert!(sim.iir_filter_enabled());
+        assert_eq!(pas_co2.iir_filter_enabled(), Some(true));
+        assert_eq!(sim.peek(Register::MeasurementMode as u8) & 0b11, 0b00);
+    });
+}
+
+#[test]
+fn test_blocking_filtered_readings() {
+    let sim = Simulator::new();
+    let mut pas_co2 = pas_co2_rs::blocking::PasCo2::new(sim.clone());
+    pas_co2
+        .soft_reset(SoftReset::DisableStepwiseReractiveIirFilter)
+        .unwrap();
+
+    let readings = pas_co2
+        .start_continuous(MeasurementPeriod::MIN, sim.delay())
+        .unwrap()
+        .with_filter(MovingAverage::<2>::new().then(Exponential::new(1.0).unwrap()))
+        .unwrap();
+    let filtered: Vec<i16> = readings
+        .take(3)
+        .map(|reading| {
+            let filtered = reading.unwrap();
+            sim.set_co2_ppm(500);
+            filtered
+        })
+        .collect();
+    assert_eq!(filtered, [400, 450, 500]);
+
+    // The filter was disabled before, so it stays disabled
+    assert!(!sim.iir_filter_enabled());
+    assert_eq!(pas_co2.iir_filter_enabled(), Some(false));
+}
+
+#[test]
+fn test_blocking_continuous_readings_idle_after_drop() {
+    let sim = Simulator::new();
+    let mut pas_co2 = pas_co2_rs::blocking::PasCo2::new(sim.clone());
+
+    let mut readings = pas_co2
+        .start_continuous(MeasurementPeriod::MIN, sim.delay())
+        .unwrap();
+    assert_eq!(readings.next_reading().unwrap(), 400);
+    // The blocking readings are an iterator
+    assert_eq!(readings.next().unwrap().unwrap(), 400);
+    drop(readings);
+
+    // The blocking driver enters idle mode immediately
+    sim.advance_ms(10_000);
+    assert_eq!(sim.peek(Register::MeasurementMode as u8) & 0b11, 0b00);
+}
+
+#[test]
+fn test_wait_for_data_ready() {
+    let (sim, mut pas_co2) = setup();
+    let mut pin = sim.int_pin();
+    block_on(async {
+        for int_pin_active_high in [true, false] {
+            pas_co2
+                .set_interrupt_config(InterruptConfig {
+                    int_pin_active_high,
+                    int_function_config: IntFunctionConfig::DataReady,
+                    alarm_crossing_up: true,
+                })
+                .await
+                .unwrap();
+
+            let start = sim.now_ms();
+            pas_co2.start_measurement().await.unwrap();
+            pas_co2.wait_for_data_ready(&mut pin).await.unwrap();
+            assert_eq!(sim.now_ms() - start, MEASUREMENT_DURATION_MS);
+
+            // The int active bit has been cleared
+            let status = pas_co2.get_measurement_status().await.unwrap();
+            assert!(status.data_ready && !status.int_active);
+            assert!(!sim.int_pin_active());
+        }
+    });
+}
+
+#[test]
+fn test_wait_for_alarm() {
+    let (sim, mut pas_co2) = setup();
+    let mut pin = sim.int_pin();
+    block_on(async {
+        pas_co2
+            .set_interrupt_config(InterruptConfig {
+                int_function_config: IntFunctionConfig::Alarm,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        pas_co2
+            .set_alarm_threshold(AlarmThresholdPpm::new(1000).unwrap())
+            .await
+            .unwrap();
+
+        sim.set_co2_ppm(500);
+        pas_co2
+            .measure_single_shot(sim.delay(), 2000)
+            .await
+            .unwrap();
+        sim.set_co2_ppm(1200);
+        pas_co2.start_measurement().await.unwrap();
+        pas_co2.wait_for_alarm(&mut pin).await.unwrap();
+
+        let status = pas_co2.get_measurement_status().await.unwrap();
+        assert!(status.alarm && !status.int_active);
+    });
+}
+
+#[test]
+fn test_wait_for_int_pin_not_configured() {
+    let (sim, mut pas_co2) = setup();
+    let mut pin = sim.int_pin();
+    block_on(async {
+        assert!(matches!(
+            pas_co2.wait_for_data_ready(&mut pin).await,
+            Err(Error::IntPinNotConfigured)
+        ));
+
+        // A pin that never becomes active is reported by the simulator
+        pas_co2
+            .set_interrupt_config(InterruptConfig {
+                int_function_config: IntFunctionConfig::Alarm,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert!(matches!(
+            pas_co2.wait_for_alarm(&mut pin).await,
+            Err(Error::Pin(_))
+        ));
+    });
+}
+
+#[test]
+fn test_blocking_wait_for_data_ready() {
+    let sim = Simulator::new();
+    let mut pin = sim.int_pin();
+    let mut pas_co2 = pas_co2_rs::blocking::PasCo2::new(sim.clone());
+
+    pas_co2
+        .set_interrupt_config(InterruptConfig {
+            int_pin_active_high: false,
+            int_function_config: IntFunctionConfig::DataReady,
+            alarm_crossing_up: true,
+        })
+        .unwrap();
+    sim.set_co2_ppm(640);
+    pas_co2.start_measurement().unwrap();
+    pas_co2.wait_for_data_ready(&mut pin).unwrap();
+    assert_eq!(pas_co2.get_co2_ppm().unwrap(), 640);
+    assert!(!sim.int_pin_active());
+}
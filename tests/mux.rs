@@ -0,0 +1,154 @@
+use embassy_futures::join::join;
+use embassy_futures::{block_on, yield_now};
+use embedded_hal::i2c::{ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation};
+use pas_co2_rs::mux::{MuxChannel, MuxError, Tca9548a, TCA9548A_ADDRESS};
+use pas_co2_rs::simulator::Simulator;
+use pas_co2_rs::{blocking, Error, I2cInterface, Interface, PasCo2};
+
+/// Bus with a TCA9548A and a simulated sensor on each channel
+#[derive(Default)]
+struct SimMux {
+    sensors: [Simulator; 8],
+    /// Channel mask written to the TCA9548A
+    mask: u8,
+    /// Number of writes to the TCA9548A
+    selections: usize,
+}
+
+impl ErrorType for SimMux {
+    type Error = ErrorKind;
+}
+
+impl I2c for SimMux {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        if address == TCA9548A_ADDRESS {
+            match operations {
+                [Operation::Write([mask])] => {
+                    self.mask = *mask;
+                    self.selections += 1;
+                    return Ok(());
+                }
+                _ => return Err(ErrorKind::Other),
+            }
+        }
+
+        // All sensors share one address, so exactly one channel must be selected
+        if self.mask.count_ones() != 1 {
+            return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+        }
+        let channel = self.mask.trailing_zeros() as usize;
+        self.sensors[channel].transaction(address, operations)
+    }
+}
+
+impl embedded_hal_async::i2c::I2c for SimMux {
+    async fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        // Let other tasks run in the middle of the transfer, like a DMA transfer would
+        yield_now().await;
+        I2c::transaction(self, address, operations)
+    }
+}
+
+#[test]
+fn test_sensors_on_channels() {
+    let bus = SimMux::default();
+    let sims = bus.sensors.clone();
+    for (i, sim) in sims.iter().enumerate() {
+        sim.set_co2_ppm(400 + 100 * i as i16);
+    }
+
+    let mux = Tca9548a::new(bus, TCA9548A_ADDRESS);
+    let mut sensors: [_; 3] = core::array::from_fn(|i| PasCo2::new(mux.channel(2 * i as u8)));
+    block_on(async {
+        for sensor in &mut sensors {
+            sensor.start_measurement().await.unwrap();
+        }
+        for sim in &sims {
+            sim.advance_ms(1000);
+        }
+        for (i, sensor) in sensors.iter_mut().enumerate() {
+            assert_eq!(sensor.get_co2_ppm().await.unwrap(), 400 + 200 * i as i16);
+        }
+    });
+
+    // Unused channels are not accessed
+    assert!(!sims[1].int_pin_active());
+    assert_eq!(
+        sims[1].peek(pas_co2_rs::regs::Register::MeasurementMode as u8),
+        0x24
+    );
+}
+
+#[test]
+fn test_channel_selected_once() {
+    let mux = Tca9548a::new(SimMux::default(), TCA9548A_ADDRESS);
+    {
+        let mut first = blocking::PasCo2::new(mux.channel(0));
+        let mut second = blocking::PasCo2::new(mux.channel(1));
+
+        first.get_status().unwrap();
+        first.get_status().unwrap();
+        second.get_status().unwrap();
+        first.get_status().unwrap();
+    }
+
+    let bus = mux.release();
+    assert_eq!(bus.selections, 3);
+    assert_eq!(bus.mask, 0b0000_0001);
+}
+
+#[test]
+fn test_nack_through_mux() {
+    let bus = SimMux::default();
+    let sim = bus.sensors[0].clone();
+    let mux = Tca9548a::new(bus, TCA9548A_ADDRESS);
+    let mut pas_co2 = PasCo2::new(mux.channel(0));
+
+    sim.inject_nacks(1);
+    let Err(Error::Interface(e)) = block_on(pas_co2.get_status()) else {
+        panic!("expected an interface error");
+    };
+    assert!(matches!(e, MuxError::I2c(ErrorKind::NoAcknowledge(_))));
+    // NACKs of the sensor are still recognized as such by the driver
+    assert!(I2cInterface::<MuxChannel<SimMux>>::is_nack(&e));
+}
+
+#[test]
+fn test_concurrent_channels() {
+    let bus = SimMux::default();
+    let sims = bus.sensors.clone();
+    sims[0].set_co2_ppm(500);
+    sims[1].set_co2_ppm(600);
+
+    let mux = Tca9548a::new(bus, TCA9548A_ADDRESS);
+    let mut first = PasCo2::new(mux.channel(0));
+    let mut second = PasCo2::new(mux.channel(1));
+    let (first, second) = block_on(join(
+        first.measure_single_shot(sims[0].delay(), 2000),
+        second.measure_single_shot(sims[1].delay(), 2000),
+    ));
+    // The transactions wait for each other instead of failing
+    assert_eq!(first.unwrap(), 500);
+    assert_eq!(second.unwrap(), 600);
+}
+
+#[test]
+fn test_blocking_transaction_busy() {
+    let mux = Tca9548a::new(SimMux::default(), TCA9548A_ADDRESS);
+    let mut first = PasCo2::new(mux.channel(0));
+    let mut second = mux.channel(1);
+    let (status, blocking_result) = block_on(join(first.get_status(), async {
+        I2c::write(&mut second, pas_co2_rs::ADDRESS, &[0x00])
+    }));
+
+    assert!(status.is_ok());
+    assert!(matches!(blocking_result, Err(MuxError::Busy)));
+}
@@ -0,0 +1,163 @@
+use std::collections::VecDeque;
+
+use embassy_futures::block_on;
+use pas_co2_rs::regs::{MeasurementPeriod, OperatingMode, PressureHpa, Register};
+use pas_co2_rs::simulator::Simulator;
+use pas_co2_rs::{blocking, PasCo2, PressureError, PressureSource};
+
+/// Barometer returning queued readings
+struct Barometer {
+    readings: VecDeque<Result<u16, ()>>,
+}
+
+impl Barometer {
+    fn new(readings: impl IntoIterator<Item = Result<u16, ()>>) -> Self {
+        Self {
+            readings: readings.into_iter().collect(),
+        }
+    }
+}
+
+impl PressureSource for Barometer {
+    type Error = ();
+
+    async fn pressure_hpa(&mut self) -> Result<u16, Self::Error> {
+        self.readings.pop_front().unwrap()
+    }
+}
+
+impl blocking::PressureSource for Barometer {
+    type Error = ();
+
+    fn pressure_hpa(&mut self) -> Result<u16, Self::Error> {
+        self.readings.pop_front().unwrap()
+    }
+}
+
+fn pressure_reference(sim: &Simulator) -> u16 {
+    let address = Register::PressureReference as u8;
+    u16::from_be_bytes([sim.peek(address), sim.peek(address + 1)])
+}
+
+#[test]
+fn test_refresh_before_measurement() {
+    let sim = Simulator::new();
+    let barometer = Barometer::new([Ok(1000), Ok(1004), Ok(1006)]);
+    let mut pas_co2 = PasCo2::new(sim.clone()).with_pressure_source(barometer, 5);
+    block_on(async {
+        pas_co2
+            .measure_single_shot(sim.delay(), 2000)
+            .await
+            .unwrap();
+        assert_eq!(pressure_reference(&sim), 1000);
+
+        // Within the allowed drift
+        pas_co2
+            .measure_single_shot(sim.delay(), 2000)
+            .await
+            .unwrap();
+        assert_eq!(pressure_reference(&sim), 1000);
+
+        pas_co2.start_measurement().await.unwrap();
+        assert_eq!(pressure_reference(&sim), 1006);
+    });
+}
+
+#[test]
+fn test_clamping() {
+    let sim = Simulator::new();
+    let barometer = Barometer::new([Ok(600), Ok(740), Ok(1200)]);
+    let mut pas_co2 = blocking::PasCo2::new(sim.clone()).with_pressure_source(barometer, 0);
+
+    assert_eq!(pas_co2.refresh_pressure().unwrap(), PressureHpa::MIN);
+    assert_eq!(pressure_reference(&sim), 750);
+    // Still clamped to the same value, no write necessary
+    sim.poke(Register::PressureReference as u8 + 1, 0xEF);
+    assert_eq!(pas_co2.refresh_pressure().unwrap(), PressureHpa::MIN);
+    assert_eq!(pressure_reference(&sim), 751);
+
+    assert_eq!(pas_co2.refresh_pressure().unwrap(), PressureHpa::MAX);
+    assert_eq!(pressure_reference(&sim), 1150);
+}
+
+#[test]
+fn test_driver_access_invalidates_pressure() {
+    let sim = Simulator::new();
+    let barometer = Barometer::new([Ok(1000), Ok(1000)]);
+    let mut pas_co2 = blocking::PasCo2::new(sim.clone()).with_pressure_source(barometer, 5);
+
+    pas_co2.refresh_pressure().unwrap();
+    pas_co2
+        .driver()
+        .set_pressure_compensation(PressureHpa::new(900).unwrap())
+        .unwrap();
+    pas_co2.refresh_pressure().unwrap();
+    assert_eq!(pressure_reference(&sim), 1000);
+}
+
+#[test]
+fn test_source_error() {
+    let sim = Simulator::new();
+    let barometer = Barometer::new([Err(())]);
+    let mut pas_co2 = blocking::PasCo2::new(sim.clone()).with_pressure_source(barometer, 5);
+
+    assert!(matches!(
+        pas_co2.measure_single_shot(sim.delay(), 2000),
+        Err(PressureError::Source(()))
+    ));
+    // No measurement was started
+    let (mut pas_co2, _) = pas_co2.release();
+    assert_eq!(
+        pas_co2.get_measurement_mode().unwrap().operating_mode,
+        OperatingMode::Idle
+    );
+}
+
+#[test]
+fn test_refresh_in_continuous_mode() {
+    let sim = Simulator::new();
+    let barometer = Barometer::new([Ok(1000), Ok(1003), Ok(1010), Err(()), Ok(1010)]);
+    let mut pas_co2 = PasCo2::new(sim.clone()).with_pressure_source(barometer, 5);
+    block_on(async {
+        let mut readings = pas_co2
+            .start_continuous(MeasurementPeriod::MIN, sim.delay())
+            .await
+            .unwrap();
+        assert_eq!(pressure_reference(&sim), 1000);
+
+        // Within the allowed drift
+        assert_eq!(readings.next_reading().await.unwrap(), 400);
+        assert_eq!(pressure_reference(&sim), 1000);
+
+        assert_eq!(readings.next_reading().await.unwrap(), 400);
+        assert_eq!(pressure_reference(&sim), 1010);
+
+        // A source error only affects the current reading
+        assert!(matches!(
+            readings.next_reading().await,
+            Err(PressureError::Source(()))
+        ));
+        assert_eq!(readings.next_reading().await.unwrap(), 400);
+        readings.stop().await.unwrap();
+    });
+
+    let (mut pas_co2, _) = pas_co2.release();
+    let mode = block_on(pas_co2.get_measurement_mode()).unwrap();
+    assert_eq!(mode.operating_mode, OperatingMode::Idle);
+}
+
+#[test]
+fn test_blocking_refresh_in_continuous_mode() {
+    let sim = Simulator::new();
+    let barometer = Barometer::new([Ok(900), Ok(900), Ok(950)]);
+    let mut pas_co2 = blocking::PasCo2::new(sim.clone()).with_pressure_source(barometer, 0);
+
+    let readings: Vec<i16> = pas_co2
+        .start_continuous(MeasurementPeriod::MIN, sim.delay())
+        .unwrap()
+        .take(2)
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(readings, [400, 400]);
+    assert_eq!(pressure_reference(&sim), 950);
+}
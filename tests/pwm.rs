@@ -0,0 +1,71 @@
+use std::collections::VecDeque;
+
+use embassy_futures::block_on;
+use pas_co2_rs::{blocking, InvalidPulse, PwmCapture, PwmConfig, PwmDecoder, PwmError, PwmPulse};
+
+/// Capture replaying a list of pulses, failing once they are used up
+struct Pulses(VecDeque<PwmPulse>);
+
+impl Pulses {
+    fn new(pulses: &[(u32, u32)]) -> Self {
+        Self(
+            pulses
+                .iter()
+                .map(|&(period_us, high_us)| PwmPulse { period_us, high_us })
+                .collect(),
+        )
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct NoEdges;
+
+impl PwmCapture for Pulses {
+    type Error = NoEdges;
+
+    async fn capture(&mut self) -> Result<PwmPulse, Self::Error> {
+        self.0.pop_front().ok_or(NoEdges)
+    }
+}
+
+impl blocking::PwmCapture for Pulses {
+    type Error = NoEdges;
+
+    fn capture(&mut self) -> Result<PwmPulse, Self::Error> {
+        self.0.pop_front().ok_or(NoEdges)
+    }
+}
+
+#[test]
+fn test_decoder() {
+    let capture = Pulses::new(&[(1000, 42), (1020, 80), (1500, 42), (1000, 0)]);
+    let mut decoder = PwmDecoder::new(capture, PwmConfig::default());
+    block_on(async {
+        assert_eq!(decoder.read_co2_ppm().await, Ok(420));
+        assert_eq!(decoder.read_co2_ppm().await, Ok(784));
+        assert_eq!(
+            decoder.read_co2_ppm().await,
+            Err(PwmError::InvalidPulse(InvalidPulse::Period))
+        );
+        assert_eq!(
+            decoder.read_co2_ppm().await,
+            Err(PwmError::InvalidPulse(InvalidPulse::HighTime))
+        );
+        assert_eq!(
+            decoder.read_co2_ppm().await,
+            Err(PwmError::Capture(NoEdges))
+        );
+    });
+    assert!(decoder.release().0.is_empty());
+}
+
+#[test]
+fn test_blocking_decoder() {
+    let capture = Pulses::new(&[(1000, 65), (1000, 1000)]);
+    let mut decoder = blocking::PwmDecoder::new(capture, PwmConfig::default());
+    assert_eq!(decoder.read_co2_ppm(), Ok(650));
+    assert_eq!(
+        decoder.read_co2_ppm(),
+        Err(PwmError::InvalidPulse(InvalidPulse::HighTime))
+    );
+}
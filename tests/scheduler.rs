@@ -0,0 +1,211 @@
+use std::cell::RefCell;
+use std::convert::Infallible;
+use std::rc::Rc;
+
+use embassy_futures::block_on;
+use embedded_hal::digital::{ErrorType, OutputPin};
+use pas_co2_rs::regs::{MeasurementMode, OperatingMode, Register};
+use pas_co2_rs::simulator::{Simulator, MEASUREMENT_DURATION_MS};
+use pas_co2_rs::{blocking, DutyCycleConfig, DutyCycleReport, Error, PasCo2};
+
+/// Emitter supply pin switching the simulator's supply and logging its state changes with the
+/// simulated time
+#[derive(Clone)]
+struct SupplyPin {
+    sim: Simulator,
+    log: Rc<RefCell<Vec<(bool, u64)>>>,
+}
+
+impl SupplyPin {
+    fn new(sim: &Simulator) -> Self {
+        Self {
+            sim: sim.clone(),
+            log: Rc::default(),
+        }
+    }
+
+    fn log(&self) -> Vec<(bool, u64)> {
+        self.log.borrow().clone()
+    }
+}
+
+impl ErrorType for SupplyPin {
+    type Error = Infallible;
+}
+
+impl OutputPin for SupplyPin {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.sim.set_emitter_supply(false);
+        self.log.borrow_mut().push((false, self.sim.now_ms()));
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.sim.set_emitter_supply(true);
+        self.log.borrow_mut().push((true, self.sim.now_ms()));
+        Ok(())
+    }
+}
+
+#[test]
+fn test_cadence() {
+    let sim = Simulator::new();
+    let mut pas_co2 = PasCo2::new(sim.clone());
+    block_on(async {
+        pas_co2
+            .set_measurement_mode(MeasurementMode {
+                operating_mode: OperatingMode::Continuous,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let mut scheduler = pas_co2
+            .start_duty_cycled(DutyCycleConfig::new(10_000), sim.delay())
+            .await
+            .unwrap();
+        for cycle in 0..3 {
+            sim.set_co2_ppm(500 + cycle as i16);
+            assert_eq!(scheduler.next_reading().await.unwrap(), 500 + cycle as i16);
+            assert_eq!(sim.now_ms(), cycle * 10_000 + MEASUREMENT_DURATION_MS);
+        }
+
+        let report = scheduler.report();
+        assert_eq!(
+            report,
+            DutyCycleReport {
+                measurements: 3,
+                active_ms: 3 * MEASUREMENT_DURATION_MS,
+                elapsed_ms: 2 * 10_000 + MEASUREMENT_DURATION_MS,
+            }
+        );
+        assert_eq!(report.duty_cycle_permille(), 142);
+
+        // The sensor is idle between measurements
+        let mode = pas_co2.get_measurement_mode().await.unwrap();
+        assert_eq!(mode.operating_mode, OperatingMode::Idle);
+    });
+}
+
+#[test]
+fn test_emitter_supply() {
+    let sim = Simulator::new();
+    let pin = SupplyPin::new(&sim);
+    let mut pas_co2 = blocking::PasCo2::new(sim.clone());
+
+    let config = DutyCycleConfig {
+        warm_up_ms: 500,
+        ..DutyCycleConfig::new(10_000)
+    };
+    let scheduler = pas_co2
+        .start_duty_cycled(config, sim.delay())
+        .unwrap()
+        .with_emitter_supply(pin.clone())
+        .unwrap();
+    let readings: Vec<i16> = scheduler.take(2).map(Result::unwrap).collect();
+    assert_eq!(readings, [400, 400]);
+
+    // On for the warm-up and the measurement, off in between
+    assert_eq!(
+        pin.log(),
+        [
+            (false, 0),
+            (true, 0),
+            (false, 1500),
+            (true, 10_000),
+            (false, 11_500)
+        ]
+    );
+}
+
+#[test]
+fn test_long_cycle() {
+    let sim = Simulator::new();
+    let mut pas_co2 = blocking::PasCo2::new(sim.clone());
+
+    // Warm-up and measurement take longer than the interval
+    let mut scheduler = pas_co2
+        .start_duty_cycled(DutyCycleConfig::new(1000), sim.delay())
+        .unwrap()
+        .with_emitter_supply(SupplyPin::new(&sim))
+        .unwrap();
+    scheduler.next_reading().unwrap();
+    scheduler.next_reading().unwrap();
+    assert_eq!(sim.now_ms(), 4000);
+    assert_eq!(scheduler.report().duty_cycle_permille(), 1000);
+}
+
+#[test]
+fn test_supply_off_after_error() {
+    let sim = Simulator::new();
+    let pin = SupplyPin::new(&sim);
+    let mut pas_co2 = blocking::PasCo2::new(sim.clone());
+
+    let mut scheduler = pas_co2
+        .start_duty_cycled(DutyCycleConfig::new(10_000), sim.delay())
+        .unwrap()
+        .with_emitter_supply(pin.clone())
+        .unwrap();
+    sim.inject_nacks(1);
+    assert!(matches!(scheduler.next_reading(), Err(Error::Interface(_))));
+    assert_eq!(pin.log().last(), Some(&(false, 1000)));
+    assert_eq!(scheduler.report().measurements, 0);
+
+    // The next cycle still starts on time
+    scheduler.next_reading().unwrap();
+    assert_eq!(pin.log()[3], (true, 10_000));
+}
+
+#[test]
+fn test_idle_after_timeout() {
+    let sim = Simulator::new();
+    let pin = SupplyPin::new(&sim);
+    let mut pas_co2 = blocking::PasCo2::new(sim.clone());
+
+    // The measurement does not complete within the timeout
+    let config = DutyCycleConfig {
+        timeout_ms: MEASUREMENT_DURATION_MS as u32 / 2,
+        ..DutyCycleConfig::new(10_000)
+    };
+    let mut scheduler = pas_co2
+        .start_duty_cycled(config, sim.delay())
+        .unwrap()
+        .with_emitter_supply(pin.clone())
+        .unwrap();
+    assert!(matches!(scheduler.next_reading(), Err(Error::Timeout)));
+    assert_eq!(scheduler.report().measurements, 0);
+    assert_eq!(
+        sim.peek(Register::MeasurementMode as u8) & 0b11,
+        0b00,
+        "sensor left in single shot mode"
+    );
+    assert_eq!(
+        pin.log().last(),
+        Some(&(false, 1000 + config.timeout_ms as u64))
+    );
+
+    // The aborted measurement does not complete later on
+    sim.advance_ms(MEASUREMENT_DURATION_MS);
+    assert_eq!(sim.peek(Register::MeasurementStatus as u8) & (1 << 4), 0);
+}
+
+#[test]
+fn test_voltage_error_cleared_after_warm_up() {
+    let sim = Simulator::new();
+    let mut pas_co2 = blocking::PasCo2::new(sim.clone());
+    pas_co2.set_fault_checking(true);
+
+    let mut scheduler = pas_co2
+        .start_duty_cycled(DutyCycleConfig::new(10_000), sim.delay())
+        .unwrap()
+        .with_emitter_supply(SupplyPin::new(&sim))
+        .unwrap();
+    // Switching the supply off raises the voltage error, which must not fail the measurements
+    for _ in 0..3 {
+        assert_eq!(scheduler.next_reading().unwrap(), 400);
+    }
+    assert_eq!(scheduler.report().measurements, 3);
+
+    // The supply is off again until the next measurement
+    assert!(pas_co2.get_status().unwrap().voltage_error);
+}
@@ -0,0 +1,140 @@
+use pas_co2_rs::air_quality::{Action, AirQuality};
+use pas_co2_rs::regs::*;
+use pas_co2_rs::{RegisterSnapshot, SensorFault};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::json;
+
+/// Serialize `value`, compare with `expected` and deserialize it again
+fn round_trip<T>(value: T, expected: serde_json::Value)
+where
+    T: Serialize + DeserializeOwned + PartialEq + Copy + core::fmt::Debug,
+{
+    let serialized = serde_json::to_value(value).unwrap();
+    assert_eq!(serialized, expected);
+    assert_eq!(serde_json::from_value::<T>(serialized).unwrap(), value);
+}
+
+#[test]
+fn test_status() {
+    round_trip(
+        Status::from(0b1001_1000),
+        json!({
+            "ready": true,
+            "pwm_dis": false,
+            "temperature_error": false,
+            "voltage_error": true,
+            "communication_error": true,
+        }),
+    );
+    round_trip(
+        MeasurementStatus::from(0b0001_0100),
+        json!({ "data_ready": true, "int_active": false, "alarm": true }),
+    );
+    round_trip(
+        ProductId::from(0x42),
+        json!({ "product": 2, "revision": 2 }),
+    );
+    round_trip(SensorFault::Communication, json!("communication"));
+}
+
+#[test]
+fn test_measurement_mode() {
+    round_trip(
+        MeasurementMode {
+            pwm_out_enable: true,
+            pwm_mode: PwmMode::PulseTrain,
+            baseline_offset_comp: BaselineOffsetCompensation::Enabled,
+            operating_mode: OperatingMode::SingleShot,
+        },
+        json!({
+            "pwm_out_enable": true,
+            "pwm_mode": "pulse_train",
+            "baseline_offset_comp": "enabled",
+            "operating_mode": "single_shot",
+        }),
+    );
+    round_trip(OperatingMode::_Reserved, json!("reserved"));
+    round_trip(BaselineOffsetCompensation::_Reserved, json!("reserved"));
+}
+
+#[test]
+fn test_interrupt_config() {
+    round_trip(
+        InterruptConfig {
+            int_pin_active_high: false,
+            int_function_config: IntFunctionConfig::EarlyMeasurementStart,
+            alarm_crossing_up: true,
+        },
+        json!({
+            "int_pin_active_high": false,
+            "int_function_config": "early_measurement_start",
+            "alarm_crossing_up": true,
+        }),
+    );
+}
+
+#[test]
+fn test_ranged_values() {
+    round_trip(MeasurementPeriod::new(60).unwrap(), json!(60));
+    round_trip(PressureHpa::new(1013).unwrap(), json!(1013));
+    round_trip(CalibrationPpm::new(400).unwrap(), json!(400));
+    round_trip(AlarmThresholdPpm::new(1000).unwrap(), json!(1000));
+
+    assert!(serde_json::from_value::<MeasurementPeriod>(json!(4)).is_err());
+    assert!(serde_json::from_value::<PressureHpa>(json!(1151)).is_err());
+    assert!(serde_json::from_value::<CalibrationPpm>(json!(349)).is_err());
+    assert!(serde_json::from_value::<AlarmThresholdPpm>(json!(-1)).is_err());
+}
+
+#[test]
+fn test_register_snapshot() {
+    round_trip(
+        RegisterSnapshot {
+            measurement_period: MeasurementPeriod::new(60).unwrap(),
+            measurement_mode: MeasurementMode::default(),
+            interrupt_config: InterruptConfig::default(),
+            alarm_threshold: AlarmThresholdPpm::MIN,
+            pressure: PressureHpa::new(1015).unwrap(),
+            calibration_reference: CalibrationPpm::new(400).unwrap(),
+        },
+        json!({
+            "measurement_period": 60,
+            "measurement_mode": {
+                "pwm_out_enable": true,
+                "pwm_mode": "single_pulse",
+                "baseline_offset_comp": "enabled",
+                "operating_mode": "idle",
+            },
+            "interrupt_config": {
+                "int_pin_active_high": true,
+                "int_function_config": "inactive",
+                "alarm_crossing_up": true,
+            },
+            "alarm_threshold": 0,
+            "pressure": 1015,
+            "calibration_reference": 400,
+        }),
+    );
+}
+
+#[test]
+fn test_register_names() {
+    assert_eq!(
+        serde_json::to_value(Register::PressureReference).unwrap(),
+        json!("pressure_reference")
+    );
+    assert_eq!(
+        serde_json::to_value(SoftReset::SaveForceCalibNvm).unwrap(),
+        json!("save_force_calib_nvm")
+    );
+    assert_eq!(
+        serde_json::to_value(SoftReset::EnableStepwiseReaciveIirFilter).unwrap(),
+        json!("enable_stepwise_reactive_iir_filter")
+    );
+}
+
+#[test]
+fn test_air_quality() {
+    round_trip(AirQuality::Unhealthy, json!("unhealthy"));
+    round_trip(Action::ConsiderVentilating, json!("consider_ventilating"));
+}
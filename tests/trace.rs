@@ -0,0 +1,101 @@
+use embassy_futures::block_on;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::{Error as _, ErrorKind, NoAcknowledgeSource};
+use pas_co2_rs::simulator::Simulator;
+use pas_co2_rs::trace::{Recorder, Replay, ReplayError, Trace, TraceOp};
+use pas_co2_rs::{blocking, Error, PasCo2};
+
+/// Delay for replays, where no time has to pass
+struct NoDelay;
+
+impl DelayNs for NoDelay {
+    fn delay_ns(&mut self, _ns: u32) {}
+}
+
+#[test]
+fn test_record_and_replay() {
+    let sim = Simulator::new();
+    sim.set_co2_ppm(850);
+    let mut recorder = Recorder::<_, 512>::new(sim.clone());
+
+    let mut pas_co2 = blocking::PasCo2::new(&mut recorder);
+    sim.inject_nacks(1);
+    assert!(pas_co2.get_status().is_err());
+    assert_eq!(pas_co2.measure_single_shot(sim.delay(), 2000).unwrap(), 850);
+    assert!(!recorder.is_overflowed());
+
+    let trace = recorder.trace();
+    let first = trace.entries().next().unwrap();
+    assert_eq!(first.op, TraceOp::WriteRead);
+    assert_eq!(
+        first.result,
+        Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address))
+    );
+
+    let mut replay = Replay::new(trace);
+    let mut pas_co2 = blocking::PasCo2::new(&mut replay);
+    match pas_co2.get_status() {
+        Err(Error::Interface(e)) => assert_eq!(
+            e.kind(),
+            ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)
+        ),
+        other => panic!("{other:?}"),
+    }
+    assert_eq!(pas_co2.measure_single_shot(NoDelay, 2000).unwrap(), 850);
+    assert!(replay.is_finished());
+    assert_eq!(replay.position(), trace.entries().count());
+}
+
+#[test]
+fn test_replay_mismatch() {
+    let mut recorder = Recorder::<_, 64>::new(Simulator::new());
+    blocking::PasCo2::new(&mut recorder).get_status().unwrap();
+
+    let mut replay = Replay::new(recorder.trace());
+    let mut pas_co2 = blocking::PasCo2::new(&mut replay);
+    assert!(matches!(
+        pas_co2.get_co2_ppm(),
+        Err(Error::Interface(ReplayError::Mismatch { index: 0 }))
+    ));
+    // A mismatch does not advance the replay
+    pas_co2.get_status().unwrap();
+    assert!(matches!(
+        pas_co2.get_status(),
+        Err(Error::Interface(ReplayError::EndOfTrace))
+    ));
+}
+
+#[test]
+fn test_replay_serialized_trace() {
+    let sim = Simulator::new();
+    let mut recorder = Recorder::<_, 64>::new(sim.clone());
+    let mut pas_co2 = PasCo2::new(&mut recorder);
+    block_on(async {
+        pas_co2.start_measurement().await.unwrap();
+        sim.advance_ms(1000);
+        assert_eq!(pas_co2.get_co2_ppm().await.unwrap(), 400);
+    });
+
+    // E.g. dumped from a device
+    let bytes = recorder.trace().as_bytes().to_vec();
+    let trace = Trace::new(&bytes).unwrap();
+
+    let mut pas_co2 = PasCo2::new(Replay::new(trace));
+    block_on(async {
+        pas_co2.start_measurement().await.unwrap();
+        assert_eq!(pas_co2.get_co2_ppm().await.unwrap(), 400);
+    });
+}
+
+#[test]
+fn test_overflow() {
+    let mut recorder = Recorder::<_, 16>::new(Simulator::new());
+    let mut pas_co2 = blocking::PasCo2::new(&mut recorder);
+    for _ in 0..3 {
+        pas_co2.get_status().unwrap();
+    }
+
+    // Each status read takes 6 bytes, so only the first two fit
+    assert!(recorder.is_overflowed());
+    assert_eq!(recorder.trace().entries().count(), 2);
+}
@@ -0,0 +1,245 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::rc::Rc;
+
+use embassy_futures::block_on;
+use embedded_hal::i2c::I2c;
+use pas_co2_rs::regs::{AlarmThresholdPpm, MeasurementMode, OperatingMode};
+use pas_co2_rs::simulator::{Simulator, MEASUREMENT_DURATION_MS};
+use pas_co2_rs::{blocking, Error, PasCo2, UartError, ADDRESS};
+
+const ACK: u8 = 0x06;
+const NAK: u8 = 0x15;
+
+#[derive(Default)]
+struct PortState {
+    /// Bytes written by the driver that do not form a complete frame yet
+    rx: Vec<u8>,
+    /// Bytes the sensor sent that were not read yet
+    tx: VecDeque<u8>,
+    /// Complete frames received, without the trailing newline
+    frames: Vec<String>,
+    /// Replies sent instead of the simulator's answer to the next frames
+    replies: VecDeque<Vec<u8>>,
+    /// Answer all frames with NAK while a single shot measurement is running
+    busy_nacks: bool,
+    /// Number of frames answered with NAK
+    nacks: usize,
+}
+
+/// Serial port speaking the sensor's ASCII protocol, backed by the [Simulator]
+#[derive(Clone)]
+struct SimUart {
+    sim: Simulator,
+    state: Rc<RefCell<PortState>>,
+}
+
+impl SimUart {
+    fn new(sim: &Simulator) -> Self {
+        Self {
+            sim: sim.clone(),
+            state: Rc::default(),
+        }
+    }
+
+    fn frames(&self) -> Vec<String> {
+        self.state.borrow().frames.clone()
+    }
+
+    fn reply_with(&self, reply: &[u8]) {
+        self.state.borrow_mut().replies.push_back(reply.to_vec());
+    }
+
+    fn busy(&self) -> bool {
+        self.state.borrow().busy_nacks && self.sim.peek(0x04) & 0b11 == 0b01
+    }
+
+    fn answer(&mut self, frame: &str) -> Vec<u8> {
+        if let Some(reply) = self.state.borrow_mut().replies.pop_front() {
+            return reply;
+        }
+        if self.busy() {
+            self.state.borrow_mut().nacks += 1;
+            return vec![NAK];
+        }
+
+        let hex = |digits: &str| u8::from_str_radix(digits, 16).unwrap();
+        match frame.split(',').collect::<Vec<_>>()[..] {
+            ["W", address, data] => match self.sim.write(ADDRESS, &[hex(address), hex(data)]) {
+                Ok(()) => vec![ACK],
+                Err(_) => vec![NAK],
+            },
+            ["R", address] => {
+                let mut value = [0];
+                match self.sim.write_read(ADDRESS, &[hex(address)], &mut value) {
+                    Ok(()) => format!("{:02X}\n", value[0]).into_bytes(),
+                    Err(_) => vec![NAK],
+                }
+            }
+            _ => vec![NAK],
+        }
+    }
+
+    fn receive(&mut self, buf: &[u8]) -> usize {
+        self.state.borrow_mut().rx.extend_from_slice(buf);
+        loop {
+            let frame = {
+                let mut state = self.state.borrow_mut();
+                let Some(end) = state.rx.iter().position(|b| *b == b'\n') else {
+                    break;
+                };
+                let frame: Vec<u8> = state.rx.drain(..=end).collect();
+                String::from_utf8(frame[..end].to_vec()).unwrap()
+            };
+            let reply = self.answer(&frame);
+            let mut state = self.state.borrow_mut();
+            state.frames.push(frame);
+            state.tx.extend(reply);
+        }
+        buf.len()
+    }
+
+    fn send(&mut self, buf: &mut [u8]) -> usize {
+        let mut state = self.state.borrow_mut();
+        let len = buf.len().min(state.tx.len());
+        for (byte, sent) in buf.iter_mut().zip(state.tx.drain(..len)) {
+            *byte = sent;
+        }
+        len
+    }
+}
+
+impl embedded_io::ErrorType for SimUart {
+    type Error = Infallible;
+}
+
+impl embedded_io::Read for SimUart {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        Ok(self.send(buf))
+    }
+}
+
+impl embedded_io::Write for SimUart {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        Ok(self.receive(buf))
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl embedded_io_async::Read for SimUart {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        Ok(self.send(buf))
+    }
+}
+
+impl embedded_io_async::Write for SimUart {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        Ok(self.receive(buf))
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_register_access() {
+    let sim = Simulator::new();
+    let uart = SimUart::new(&sim);
+    let mut pas_co2 = PasCo2::new_uart(uart.clone());
+    block_on(async {
+        assert!(pas_co2.get_status().await.unwrap().ready);
+        assert_eq!(uart.frames(), ["R,01"]);
+
+        // Two byte registers are transferred with one frame per byte
+        pas_co2
+            .set_alarm_threshold(AlarmThresholdPpm::new(1000).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(uart.frames()[1..], ["W,09,03", "W,0A,E8"]);
+        assert_eq!([sim.peek(0x09), sim.peek(0x0A)], [0x03, 0xE8]);
+
+        sim.poke(0x0A, 0xF4);
+        let threshold = pas_co2.get_alarm_threshold().await.unwrap();
+        assert_eq!(threshold.get(), 1012);
+        assert_eq!(uart.frames()[3..], ["R,09", "R,0A"]);
+    });
+}
+
+#[test]
+fn test_nack() {
+    let sim = Simulator::new();
+    let mut pas_co2 = PasCo2::new_uart(SimUart::new(&sim));
+    block_on(async {
+        sim.inject_nacks(1);
+        let result = pas_co2
+            .set_measurement_mode(MeasurementMode::default())
+            .await;
+        assert!(matches!(result, Err(Error::Interface(UartError::Nack))));
+
+        sim.inject_nacks(1);
+        let result = pas_co2.get_status().await;
+        assert!(matches!(result, Err(Error::Interface(UartError::Nack))));
+
+        assert!(pas_co2.get_status().await.is_ok());
+    });
+}
+
+#[test]
+fn test_invalid_response() {
+    let sim = Simulator::new();
+    let uart = SimUart::new(&sim);
+    let mut pas_co2 = blocking::PasCo2::new_uart(uart.clone());
+
+    // Short reply
+    uart.reply_with(b"0");
+    let result = pas_co2.get_status();
+    assert!(matches!(
+        result,
+        Err(Error::Interface(UartError::InvalidResponse))
+    ));
+
+    // No hex digits
+    uart.reply_with(b"G1\n");
+    let result = pas_co2.get_status();
+    assert!(matches!(
+        result,
+        Err(Error::Interface(UartError::InvalidResponse))
+    ));
+
+    // Neither ACK nor NAK
+    uart.reply_with(b"?");
+    let result = pas_co2.set_measurement_mode(MeasurementMode::default());
+    assert!(matches!(
+        result,
+        Err(Error::Interface(UartError::InvalidResponse))
+    ));
+
+    assert!(pas_co2.get_status().unwrap().ready);
+}
+
+#[test]
+fn test_nack_while_measuring() {
+    let sim = Simulator::new();
+    let uart = SimUart::new(&sim);
+    let mut pas_co2 = PasCo2::new_uart(uart.clone());
+    block_on(async {
+        sim.set_co2_ppm(730);
+        uart.state.borrow_mut().busy_nacks = true;
+
+        let co2_ppm = pas_co2
+            .measure_single_shot(sim.delay(), 2000)
+            .await
+            .unwrap();
+        assert_eq!(co2_ppm, 730);
+        assert_eq!(sim.now_ms(), MEASUREMENT_DURATION_MS);
+        assert!(uart.state.borrow().nacks > 0);
+
+        let mode = pas_co2.get_measurement_mode().await.unwrap();
+        assert_eq!(mode.operating_mode, OperatingMode::Idle);
+    });
+}